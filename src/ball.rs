@@ -1,5 +1,11 @@
 use super::BottomWall;
+use crate::ball_lock::Locked;
+use crate::layers::Layer;
+use crate::rolling::{Roll, ball_marker};
+use crate::rules::RuleTimers;
+use crate::table::TableConfig;
 use avian2d::prelude::*;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 
@@ -7,67 +13,168 @@ pub struct BallPlugin;
 
 impl Plugin for BallPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_ball)
-            .add_systems(Update, handle_ball_intersections_with_bottom_wall);
+        app.add_message::<BallServed>()
+            .add_systems(Startup, spawn_ball)
+            .add_systems(FixedUpdate, handle_ball_intersections_with_bottom_wall)
+            .add_systems(
+                FixedUpdate,
+                apply_ball_tuning.run_if(resource_changed::<TableConfig>),
+            );
     }
 }
 
+pub(crate) const BALL_RADIUS: f32 = crate::PIXELS_PER_METER * 0.03;
+
 #[derive(Component)]
 pub(crate) struct Ball;
 
-fn spawn_ball(mut commands: Commands) {
-    let ball_pos = Vec2::new(
-        crate::PIXELS_PER_METER * 0.3,
-        crate::PIXELS_PER_METER * -0.2,
-    );
+/// Why a ball was put in the shooter lane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Serve {
+    /// The player's next ball, once none is left in play.
+    NextBall,
+    /// Stands in for a ball that went into the lock or drained while the ball
+    /// save was on, to keep play going.
+    Replacement,
+}
+
+/// A ball was served to the shooter lane.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct BallServed {
+    pub ball: Entity,
+    pub serve: Serve,
+}
+
+/// Puts new balls in the shooter lane and announces them with [`BallServed`].
+#[derive(SystemParam)]
+pub(crate) struct BallServer<'w, 's> {
+    commands: Commands<'w, 's>,
+    config: Res<'w, TableConfig>,
+    served: MessageWriter<'w, BallServed>,
+}
+
+impl BallServer<'_, '_> {
+    pub fn serve(&mut self, serve: Serve) {
+        let ball_pos = Vec2::new(
+            crate::PIXELS_PER_METER * 0.3,
+            crate::PIXELS_PER_METER * -0.2,
+        );
+
+        let ball = self
+            .commands
+            .spawn((
+                Name::from("Ball"),
+                ball_body(&self.config),
+                Transform::from_xyz(ball_pos.x, ball_pos.y, 0.0),
+                Ball,
+            ))
+            .id();
+        self.served.write(BallServed { ball, serve });
+    }
+}
+
+/// Serves the next ball.
+pub(crate) fn spawn_ball(mut server: BallServer) {
+    server.serve(Serve::NextBall);
+}
 
+/// Shape and physics of a ball, whether in play or held captive.
+pub(crate) fn ball_body(config: &TableConfig) -> impl Bundle + use<> {
     let shape_ball = shapes::Circle {
-        radius: crate::PIXELS_PER_METER * 0.03,
+        radius: BALL_RADIUS,
         center: Vec2::ZERO,
     };
     //let bevy_shape = Circle::new(shape_ball.radius);
 
-    commands.spawn((
-        Name::from("Ball"),
+    (
         ShapeBuilder::with(&shape_ball)
             .fill(Color::BLACK)
             .stroke((bevy::color::palettes::css::TEAL, 2.0))
             .build(),
-        Transform::from_xyz(ball_pos.x, ball_pos.y, 0.0),
         Collider::circle(shape_ball.radius),
         CollisionEventsEnabled,
-        // Max combine rule so the ball keeps its own bounciness off walls that set no Restitution
-        // (default Average would halve it against a 0.0 wall).
-        Restitution::new(0.4).with_combine_rule(CoefficientCombine::Max),
-        Friction::from(0.2),
-        // a standard pinball ball mass is about 80 grams
-        Mass::from(0.08),
+        // The materials hold the coefficients against the ball; the ball only scales them.
+        Restitution::new(config.ball_restitution).with_combine_rule(CoefficientCombine::Multiply),
+        Friction::new(config.ball_friction).with_combine_rule(CoefficientCombine::Multiply),
+        Mass::from(config.ball_mass),
+        AngularDamping(config.ball_spin_damping),
+        Roll::default(),
         //MassPropertiesBundle::from_shape(&bevy_shape, 10.0),
         RigidBody::Dynamic,
-        Ball,
+        Layer::Playfield.only(),
         SleepingDisabled,
         SweptCcd::default(),
-    ));
+        // Swept CCD already stops fast balls tunnelling. A wide speculative margin
+        // only makes them catch on the joints of curved walls and rails ahead.
+        SpeculativeMargin(BALL_RADIUS * 0.25),
+        // speed-dependent restitution against the table's materials and other balls
+        ActiveCollisionHooks::MODIFY_CONTACTS,
+        children![ball_marker(Vec3::Z), ball_marker(Vec3::NEG_Z)],
+    )
 }
 
 fn handle_ball_intersections_with_bottom_wall(
     mut collision_reader: MessageReader<CollisionStart>,
-    query_ball: Query<&Ball>,
+    query_ball: Query<Has<Locked>, With<Ball>>,
     query_bottom_wall: Query<&BottomWall>,
     mut commands: Commands,
+    mut server: BallServer,
+    mut timers: ResMut<RuleTimers>,
 ) {
-    let mut ball_entity = None;
+    let mut drained = Vec::new();
     for event in collision_reader.read() {
         if let (Some(entity1), Some(entity2)) = (event.body1, event.body2) {
-            if query_ball.contains(entity1) && query_bottom_wall.contains(entity2) {
-                ball_entity = Some(entity1)
+            let ball = if query_ball.contains(entity1) && query_bottom_wall.contains(entity2) {
+                entity1
             } else if query_ball.contains(entity2) && query_bottom_wall.contains(entity1) {
-                ball_entity = Some(entity2)
+                entity2
+            } else {
+                continue;
             };
+            if !drained.contains(&ball) {
+                drained.push(ball);
+            }
         }
     }
-    if let Some(ball) = ball_entity {
-        commands.entity(ball).despawn();
-        spawn_ball(commands);
+    if drained.is_empty() {
+        return;
+    }
+    for ball in &drained {
+        commands.entity(*ball).despawn();
+    }
+
+    // Locked balls are still on the table, but out of play until multiball.
+    let in_play = query_ball.iter().filter(|locked| !locked).count();
+    if in_play > drained.len() {
+        return;
+    }
+    // A save gives the same ball back once, without using up the next one.
+    if timers.ball_save_active() {
+        timers.ball_save.finish();
+        server.serve(Serve::Replacement);
+    } else {
+        server.serve(Serve::NextBall);
+    }
+}
+
+fn apply_ball_tuning(
+    config: Res<TableConfig>,
+    mut balls: Query<
+        (
+            &mut Restitution,
+            &mut Friction,
+            &mut Mass,
+            &mut AngularDamping,
+        ),
+        // captive balls too
+        With<Roll>,
+    >,
+) {
+    for (mut restitution, mut friction, mut mass, mut spin_damping) in &mut balls {
+        restitution.coefficient = config.ball_restitution;
+        friction.dynamic_coefficient = config.ball_friction;
+        friction.static_coefficient = config.ball_friction;
+        mass.0 = config.ball_mass;
+        spin_damping.0 = config.ball_spin_damping;
     }
 }
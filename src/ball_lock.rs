@@ -0,0 +1,290 @@
+//! Ball lock.
+//!
+//! A short lane down the right side, against the launcher wall, closed at the
+//! bottom by a post. Balls that roll down onto the post are locked: they stay
+//! where they are, out of play, and a new ball is served unless another one
+//! is still in play. [`StartMultiball`] drops the post for a moment so every
+//! locked ball rolls out at once. While the lock is full, the rules close the
+//! lane with the lock diverter.
+
+use crate::ball::{Ball, BallServer, Serve};
+use crate::materials::TableMaterial;
+use crate::walls::{GUIDE_THICKNESS, curved_wall, straight};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct BallLockPlugin;
+
+impl Plugin for BallLockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BallLock>()
+            .add_message::<StartMultiball>()
+            .add_systems(Startup, spawn_ball_lock)
+            .add_systems(
+                FixedUpdate,
+                (lock_balls, start_multiball, close_lock_post).chain(),
+            );
+    }
+}
+
+/// Balls the lane holds.
+pub const LOCK_CAPACITY: usize = 2;
+/// How long the post stays down to let the locked balls out.
+const POST_OPEN_TIME: f32 = 1.0;
+
+/// Releases the locked balls.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct StartMultiball;
+
+#[derive(Resource, Default)]
+pub struct BallLock {
+    pub locked: usize,
+    /// Time left until the post comes back up; zero while it is up.
+    post_down_for: f32,
+}
+
+impl BallLock {
+    pub fn is_full(&self) -> bool {
+        self.locked >= LOCK_CAPACITY
+    }
+}
+
+/// On a ball sitting in the lock.
+#[derive(Component)]
+pub(crate) struct Locked;
+
+#[derive(Component)]
+struct LockSensor;
+
+#[derive(Component)]
+struct LockPost;
+
+fn spawn_ball_lock(mut commands: Commands) {
+    let guide_x = crate::PIXELS_PER_METER * 0.155;
+    // the launcher wall's inner face is the other side of the lane
+    let wall_x = crate::PIXELS_PER_METER * 0.235;
+    let post_y = crate::PIXELS_PER_METER * -0.035;
+
+    commands.spawn(curved_wall(
+        "Lock Lane Guide",
+        &[straight(
+            Vec2::new(guide_x, crate::PIXELS_PER_METER * 0.1),
+            Vec2::new(guide_x, post_y + crate::PIXELS_PER_METER * 0.005),
+        )],
+        GUIDE_THICKNESS,
+        TableMaterial::Metal,
+    ));
+
+    let shape_post = shapes::Rectangle {
+        extents: Vec2::new(wall_x - guide_x, GUIDE_THICKNESS),
+        origin: shapes::RectangleOrigin::Center,
+        radii: None,
+    };
+    commands.spawn((
+        Name::from("Lock Post"),
+        ShapeBuilder::with(&shape_post)
+            .fill(bevy::color::palettes::css::SILVER)
+            .build(),
+        RigidBody::Static,
+        Collider::rectangle(shape_post.extents.x, shape_post.extents.y),
+        TableMaterial::Metal,
+        Transform::from_xyz((guide_x + wall_x) / 2.0, post_y, 0.0),
+        LockPost,
+    ));
+
+    // Covers the lane as high as the locked balls stack.
+    let sensor_height = crate::PIXELS_PER_METER * 0.1;
+    commands.spawn((
+        Name::from("Lock"),
+        RigidBody::Static,
+        Collider::rectangle(wall_x - guide_x - GUIDE_THICKNESS, sensor_height),
+        Sensor,
+        Transform::from_xyz(
+            (guide_x + wall_x) / 2.0,
+            post_y + crate::PIXELS_PER_METER * 0.015 + sensor_height / 2.0,
+            0.0,
+        ),
+        LockSensor,
+    ));
+}
+
+fn lock_balls(
+    mut collision_reader: MessageReader<CollisionStart>,
+    sensors: Query<(), With<LockSensor>>,
+    balls: Query<(), (With<Ball>, Without<Locked>)>,
+    mut lock: ResMut<BallLock>,
+    mut commands: Commands,
+    mut server: BallServer,
+) {
+    let mut locked_now = 0;
+    for event in collision_reader.read() {
+        let (Some(body1), Some(body2)) = (event.body1, event.body2) else {
+            continue;
+        };
+        let ball = if sensors.contains(body1) {
+            body2
+        } else if sensors.contains(body2) {
+            body1
+        } else {
+            continue;
+        };
+        // Balls roll straight through while the post is down.
+        if !balls.contains(ball) || lock.is_full() || lock.post_down_for > 0.0 {
+            continue;
+        }
+        commands.entity(ball).insert(Locked);
+        lock.locked += 1;
+        locked_now += 1;
+    }
+    // Like the drain: only serve when no ball is left in play.
+    if locked_now > 0 && balls.iter().count() <= locked_now {
+        server.serve(Serve::Replacement);
+    }
+}
+
+fn start_multiball(
+    mut start: MessageReader<StartMultiball>,
+    mut lock: ResMut<BallLock>,
+    posts: Query<Entity, With<LockPost>>,
+    locked: Query<Entity, With<Locked>>,
+    mut commands: Commands,
+) {
+    if start.read().count() == 0 {
+        return;
+    }
+    lock.post_down_for = POST_OPEN_TIME;
+    for entity in &posts {
+        commands
+            .entity(entity)
+            .insert((ColliderDisabled, Visibility::Hidden));
+    }
+    for ball in &locked {
+        commands.entity(ball).remove::<Locked>();
+    }
+    lock.locked = 0;
+}
+
+fn close_lock_post(
+    time: Res<Time>,
+    mut lock: ResMut<BallLock>,
+    posts: Query<Entity, With<LockPost>>,
+    mut commands: Commands,
+) {
+    if lock.post_down_for <= 0.0 {
+        return;
+    }
+    lock.post_down_for -= time.delta_secs();
+    if lock.post_down_for > 0.0 {
+        return;
+    }
+    lock.post_down_for = 0.0;
+    for entity in &posts {
+        commands
+            .entity(entity)
+            .remove::<ColliderDisabled>()
+            .insert(Visibility::Inherited);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn drop_into_lock(sim: &mut Sim, ball: Entity) {
+        let lane = Vec2::new(0.195, 0.12) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, lane, Vec2::ZERO);
+        sim.step(96);
+    }
+
+    /// Locks the first two balls served and returns them.
+    fn fill_lock(sim: &mut Sim) -> [Entity; 2] {
+        let first = sim.ball();
+        drop_into_lock(sim, first);
+        let served = sim.balls().into_iter().find(|ball| *ball != first).unwrap();
+        drop_into_lock(sim, served);
+        [first, served]
+    }
+
+    #[test]
+    fn locked_balls_stay_put_until_multiball() {
+        let mut sim = Sim::new();
+        let [first, served] = fill_lock(&mut sim);
+        assert_eq!(sim.app.world().resource::<BallLock>().locked, 2);
+        // a third ball was served for play to go on
+        assert_eq!(sim.balls().len(), 3);
+
+        sim.step(64);
+        let post_y = crate::PIXELS_PER_METER * -0.035;
+        for ball in [first, served] {
+            let (position, _) = sim.ball_state(ball).unwrap();
+            assert!(
+                position.y > post_y,
+                "locked ball at {position} fell through"
+            );
+        }
+
+        sim.app.world_mut().write_message(StartMultiball);
+        sim.step(32);
+        for ball in [first, served] {
+            let (position, _) = sim.ball_state(ball).expect("released ball drained");
+            assert!(position.y < post_y, "ball still locked at {position}");
+        }
+        assert_eq!(sim.app.world().resource::<BallLock>().locked, 0);
+    }
+
+    #[test]
+    fn new_ball_is_served_only_when_the_last_one_drains() {
+        let mut sim = Sim::new();
+        let first = sim.ball();
+        let second = sim.spawn_ball();
+        let drain = Vec2::new(-0.05, -0.55) * crate::PIXELS_PER_METER;
+
+        sim.place_ball(first, drain, Vec2::new(0.0, -300.0));
+        sim.step(16);
+        assert!(sim.ball_state(first).is_none());
+        assert_eq!(sim.balls(), vec![second]);
+
+        sim.place_ball(second, drain, Vec2::new(0.0, -300.0));
+        sim.step(16);
+        let balls = sim.balls();
+        assert_eq!(balls.len(), 1);
+        assert_ne!(balls[0], second);
+    }
+
+    #[test]
+    fn full_lock_turns_the_next_ball_away() {
+        let mut sim = Sim::new();
+        let locked = fill_lock(&mut sim);
+        let third = sim
+            .balls()
+            .into_iter()
+            .find(|ball| !locked.contains(ball))
+            .unwrap();
+
+        // Above the diverter, which has closed the lane by now.
+        let above_lane = Vec2::new(0.195, 0.2) * crate::PIXELS_PER_METER;
+        sim.place_ball(third, above_lane, Vec2::ZERO);
+        sim.step(128);
+
+        assert_eq!(sim.app.world().resource::<BallLock>().locked, 2);
+        let guide_x = crate::PIXELS_PER_METER * 0.155;
+        let (position, _) = sim.ball_state(third).expect("turned away ball drained");
+        assert!(
+            position.x < guide_x,
+            "third ball stuck on top of the lock at {position}"
+        );
+    }
+
+    #[test]
+    fn no_ball_is_served_for_a_lock_while_another_is_in_play() {
+        let mut sim = Sim::new();
+        let first = sim.ball();
+        let second = sim.spawn_ball();
+        drop_into_lock(&mut sim, first);
+        assert_eq!(sim.app.world().resource::<BallLock>().locked, 1);
+        assert_eq!(sim.balls().len(), 2);
+        assert!(sim.ball_state(second).is_some());
+    }
+}
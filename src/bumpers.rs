@@ -1,26 +1,26 @@
 use crate::ball::Ball;
+use crate::lamps::{LampCommand, LampState, lamp};
+use crate::materials::TableMaterial;
+use crate::table::TableConfig;
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 
-const BUMPER_PULSE_FORCE: f32 = 35.0;
-
 pub struct BumpersPlugin;
 
 impl Plugin for BumpersPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_bumpers)
-            .add_systems(Update, handle_bumper_collisions)
-            .add_systems(Update, hide_expired_bumper_indicators);
+            .add_systems(FixedUpdate, handle_bumper_collisions);
     }
 }
 
-#[derive(Component)]
-struct Bumper;
+/// How long a bumper's lamp flashes when hit.
+const BUMPER_LIGHT_TIME: f32 = 0.2;
 
 #[derive(Component)]
-struct BumperIndicator {
-    timestamp_last_hit: f64,
+struct Bumper {
+    lamp: String,
 }
 
 fn spawn_bumpers(mut commands: Commands) {
@@ -50,10 +50,7 @@ fn spawn_single_bumper(commands: &mut Commands, name: String, position: Vec2) {
         center: Vec2::ZERO,
     };
 
-    let shape_indicator = shapes::Circle {
-        radius: inner_radius,
-        center: Vec2::ZERO,
-    };
+    let lamp_name = format!("{name} Lamp");
 
     // Create the main bumper
     commands.spawn((
@@ -64,43 +61,38 @@ fn spawn_single_bumper(commands: &mut Commands, name: String, position: Vec2) {
             .build(),
         Transform::from_xyz(position.x, position.y, 0.0),
         RigidBody::Static,
-        Restitution::new(0.7),
+        TableMaterial::Plastic,
         Collider::circle(outer_radius),
-        Bumper,
-        children![(
-            Name::from("BumperIndicator"),
-            ShapeBuilder::with(&shape_indicator)
-                .fill(bevy::color::palettes::css::GREEN)
-                .build(),
-            Visibility::Hidden,
-            Transform::from_xyz(0.0, 0.0, 0.1), // Slightly above bumper
-            BumperIndicator {
-                timestamp_last_hit: 0.0,
-            },
+        children![lamp(
+            &lamp_name,
+            Vec2::ZERO,
+            inner_radius,
+            bevy::color::palettes::css::GREEN.into(),
+            1.0,
+            LampState::Off,
         )],
+        Bumper { lamp: lamp_name },
     ));
 }
 
 fn handle_bumper_collisions(
-    bumper_query: Query<(&Children, Entity, &Transform), With<Bumper>>,
-    mut indicator_query: Query<(Entity, &mut BumperIndicator), With<BumperIndicator>>,
+    bumper_query: Query<(&Bumper, Entity, &Transform)>,
     mut ball_query: Query<(&Transform, Forces), With<Ball>>,
-    time: Res<Time>,
+    config: Res<TableConfig>,
     mut contact_events: MessageReader<CollisionStart>,
-    mut commands: Commands,
+    mut lamps: MessageWriter<LampCommand>,
 ) {
     for contact_event in contact_events.read() {
-        for (children, bumper_entity, bumper_transform) in bumper_query.iter() {
+        for (bumper, bumper_entity, bumper_transform) in bumper_query.iter() {
             if let (Some(h1), Some(h2)) = (contact_event.body1, contact_event.body2)
                 && (h1 == bumper_entity || h2 == bumper_entity)
             {
-                // Activate the indicator
-                for child in children.iter() {
-                    if let Ok((entity, mut indicator)) = indicator_query.get_mut(child) {
-                        indicator.timestamp_last_hit = time.elapsed_secs_f64();
-                        commands.entity(entity).insert(Visibility::Visible);
-                    }
-                }
+                lamps.write(LampCommand::new(
+                    bumper.lamp.clone(),
+                    LampState::Flash {
+                        duration: BUMPER_LIGHT_TIME,
+                    },
+                ));
 
                 // Apply outward pulse to the ball
                 let ball_entity = if h1 == bumper_entity { h2 } else { h1 };
@@ -110,26 +102,37 @@ fn handle_bumper_collisions(
                     let ball_pos = ball_transform.translation.truncate();
                     let direction = (ball_pos - bumper_pos).normalize();
 
-                    forces.apply_linear_impulse(direction * BUMPER_PULSE_FORCE);
+                    forces.apply_linear_impulse(direction * config.bumper_pulse);
                 }
             }
         }
     }
 }
 
-const BUMPER_LIGHT_TIME: f64 = 0.2;
-
-fn hide_expired_bumper_indicators(
-    mut query: Query<(Entity, &BumperIndicator), With<BumperIndicator>>,
-    time: Res<Time>,
-    mut commands: Commands,
-) {
-    for (entity, indicator) in query.iter_mut() {
-        if indicator.timestamp_last_hit > 0.0 {
-            let elapsed = time.elapsed_secs_f64() - indicator.timestamp_last_hit;
-            if elapsed > BUMPER_LIGHT_TIME {
-                commands.entity(entity).insert(Visibility::Hidden);
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    #[test]
+    fn ball_dropped_on_bumper_is_kicked_away() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        // Slightly off-centre above the lower bumper so the kick has a sideways component.
+        let bumper = Vec2::new(0.0, crate::PIXELS_PER_METER * 0.2);
+        sim.place_ball(ball, bumper + Vec2::new(5.0, 100.0), Vec2::ZERO);
+
+        let mut fastest = 0.0_f32;
+        for _ in 0..64 {
+            sim.step(1);
+            let (_, velocity) = sim.ball_state(ball).unwrap();
+            fastest = fastest.max(velocity.length());
         }
+
+        // Free fall alone reaches ~250 px/s; the pulse adds ~35 / 0.08 kg on top.
+        assert!(
+            fastest > 400.0,
+            "bumper barely kicked the ball: {fastest} px/s"
+        );
     }
 }
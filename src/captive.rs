@@ -0,0 +1,151 @@
+//! Captive ball.
+//!
+//! A second ball that never leaves its short lane on the left, under the
+//! upper playfield and between the two ramp entrances, so a shot at it stays
+//! on the playfield layer. It rests on two posts at the mouth of the lane
+//! with its bottom poking out between them; the ball in play hits it from
+//! below and knocks it up the lane. Reaching the sensor at the top end sends
+//! [`CaptiveBallHit`].
+
+use crate::ball::ball_body;
+use crate::materials::TableMaterial;
+use crate::table::TableConfig;
+use crate::walls::{GUIDE_THICKNESS, curved_wall, straight};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+pub struct CaptivePlugin;
+
+impl Plugin for CaptivePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CaptiveBallHit>()
+            .add_systems(Startup, spawn_captive_ball)
+            .add_systems(FixedUpdate, detect_captive_ball_hits);
+    }
+}
+
+/// Lane centre line and the height of its mouth and top end. The mouth stays
+/// clear of balls dropping out of the upper playfield drain just below, and a
+/// ball coming straight up at it passes between the Upper Ramp and Left Ramp
+/// entrances.
+const LANE_X: f32 = crate::PIXELS_PER_METER * -0.205;
+const LANE_BOTTOM: f32 = crate::PIXELS_PER_METER * 0.05;
+const LANE_TOP: f32 = crate::PIXELS_PER_METER * 0.25;
+/// Wide enough for the ball to run freely.
+const LANE_WIDTH: f32 = crate::PIXELS_PER_METER * 0.07;
+/// Gap between the posts the ball rests on, narrower than the ball.
+const MOUTH_WIDTH: f32 = crate::PIXELS_PER_METER * 0.05;
+
+/// The captive ball was knocked all the way up its lane.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct CaptiveBallHit;
+
+#[derive(Component)]
+pub(crate) struct CaptiveBall;
+
+#[derive(Component)]
+struct CaptiveLaneEnd;
+
+fn spawn_captive_ball(mut commands: Commands, config: Res<TableConfig>) {
+    let left = LANE_X - (LANE_WIDTH + GUIDE_THICKNESS) / 2.0;
+    let right = LANE_X + (LANE_WIDTH + GUIDE_THICKNESS) / 2.0;
+    commands.spawn(curved_wall(
+        "Captive Lane",
+        &[
+            straight(Vec2::new(left, LANE_BOTTOM), Vec2::new(left, LANE_TOP)),
+            straight(Vec2::new(left, LANE_TOP), Vec2::new(right, LANE_TOP)),
+            straight(Vec2::new(right, LANE_TOP), Vec2::new(right, LANE_BOTTOM)),
+        ],
+        GUIDE_THICKNESS,
+        TableMaterial::Metal,
+    ));
+
+    for (side, offset) in [("Left", -1.0), ("Right", 1.0)] {
+        let post = Vec2::new(
+            LANE_X + offset * (MOUTH_WIDTH + GUIDE_THICKNESS) / 2.0,
+            LANE_BOTTOM,
+        );
+        commands.spawn((
+            Name::from(format!("Captive Lane {side} Post")),
+            RigidBody::Static,
+            Collider::circle(GUIDE_THICKNESS / 2.0),
+            TableMaterial::Metal,
+            Transform::from_translation(post.extend(0.0)),
+        ));
+    }
+
+    let sensor_height = crate::PIXELS_PER_METER * 0.02;
+    commands.spawn((
+        Name::from("Captive Lane End"),
+        RigidBody::Static,
+        Collider::rectangle(LANE_WIDTH, sensor_height),
+        Sensor,
+        Transform::from_xyz(LANE_X, LANE_TOP - sensor_height, 0.0),
+        CaptiveLaneEnd,
+    ));
+
+    commands.spawn((
+        Name::from("Captive Ball"),
+        ball_body(&config),
+        Transform::from_xyz(LANE_X, LANE_BOTTOM + crate::PIXELS_PER_METER * 0.03, 0.0),
+        CaptiveBall,
+    ));
+}
+
+fn detect_captive_ball_hits(
+    mut collision_reader: MessageReader<CollisionStart>,
+    ends: Query<(), With<CaptiveLaneEnd>>,
+    captive_balls: Query<(), With<CaptiveBall>>,
+    mut hits: MessageWriter<CaptiveBallHit>,
+) {
+    for event in collision_reader.read() {
+        let (Some(body1), Some(body2)) = (event.body1, event.body2) else {
+            continue;
+        };
+        if (ends.contains(body1) && captive_balls.contains(body2))
+            || (ends.contains(body2) && captive_balls.contains(body1))
+        {
+            hits.write(CaptiveBallHit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn captive_position(sim: &mut Sim) -> Vec2 {
+        let world = sim.app.world_mut();
+        let mut captive = world.query_filtered::<&Position, With<CaptiveBall>>();
+        captive.single(world).unwrap().0
+    }
+
+    #[test]
+    fn captive_ball_stays_in_its_lane_when_hit() {
+        let mut sim = Sim::new();
+        sim.step(64);
+        let rest = captive_position(&mut sim);
+        assert!((rest.x - LANE_X).abs() < 2.0 && rest.y > LANE_BOTTOM);
+
+        // Straight up from below the Upper Ramp entrance into the bottom of the captive ball.
+        let ball = sim.ball();
+        let below = Vec2::new(LANE_X, crate::PIXELS_PER_METER * -0.22);
+        sim.place_ball(ball, below, Vec2::new(0.0, 700.0));
+
+        let mut highest = f32::MIN;
+        for _ in 0..96 {
+            sim.step(1);
+            let position = captive_position(&mut sim);
+            assert!((position.x - LANE_X).abs() < LANE_WIDTH / 2.0);
+            highest = highest.max(position.y);
+        }
+        assert!(
+            highest > LANE_TOP - crate::PIXELS_PER_METER * 0.06,
+            "captive ball only got to {highest}"
+        );
+        // and falls back to rest on the posts
+        sim.step(128);
+        assert!(captive_position(&mut sim).distance(rest) < 5.0);
+    }
+}
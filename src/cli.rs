@@ -0,0 +1,83 @@
+//! Command-line options.
+
+use clap::{ArgGroup, Parser};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "A simple 2D pinball table")]
+#[command(group(ArgGroup::new("end").args(["replay", "ticks"]).multiple(true)))]
+pub struct Cli {
+    /// Table settings file (RON) with the physics parameters; the tuning panel saves here too.
+    #[arg(long, value_name = "FILE")]
+    pub table: Option<PathBuf>,
+
+    /// Window size in logical pixels.
+    #[arg(long, value_name = "WIDTHxHEIGHT", default_value = "360x640", value_parser = parse_resolution)]
+    pub resolution: (u32, u32),
+
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Wait for vertical sync before presenting a frame.
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    pub vsync: bool,
+
+    /// Start with colliders, joints and contacts drawn (toggle with F1).
+    #[arg(long)]
+    pub physics_debug: bool,
+
+    /// Start with the egui world inspector open (toggle with F2).
+    #[arg(long)]
+    pub inspector: bool,
+
+    /// Run without a window as fast as possible, until the replay ends or --ticks is reached.
+    #[arg(long, requires = "end")]
+    pub headless: bool,
+
+    /// Play back the inputs and table settings recorded in this file.
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Record this game's inputs to this file when it exits.
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Export every ball's motion and contacts to this file as JSON lines.
+    #[arg(long, value_name = "FILE")]
+    pub trajectory: Option<PathBuf>,
+
+    /// Quit after this many physics ticks.
+    #[arg(long)]
+    pub ticks: Option<u64>,
+}
+
+fn parse_resolution(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {value}"))?;
+    let parse = |side: &str| side.trim().parse::<u32>().map_err(|err| err.to_string());
+    Ok((parse(width)?, parse(height)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_resolution() {
+        assert_eq!(parse_resolution("720x1280"), Ok((720, 1280)));
+        assert!(parse_resolution("720").is_err());
+    }
+
+    #[test]
+    fn headless_needs_an_end() {
+        assert!(Cli::try_parse_from(["pinball2d", "--headless"]).is_err());
+        assert!(Cli::try_parse_from(["pinball2d", "--headless", "--ticks", "640"]).is_ok());
+    }
+}
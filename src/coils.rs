@@ -0,0 +1,283 @@
+//! Solenoid-driven playfield devices.
+//!
+//! Every device has a [`Coil`] that rules switch with [`CoilCommand`]s
+//! addressed by name. A diverter is a kinematic flap that swings between its
+//! rest and energized angles; an up-post rises out of the playfield and only
+//! blocks the ball once it is all the way up.
+
+use crate::materials::TableMaterial;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct CoilsPlugin;
+
+impl Plugin for CoilsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CoilCommand>()
+            .add_systems(Startup, spawn_coil_devices)
+            .add_systems(
+                FixedUpdate,
+                (handle_coil_commands, swing_diverters, move_up_posts).chain(),
+            );
+    }
+}
+
+/// Fastest a diverter flap swings, in rad/s.
+const DIVERTER_SPEED: f32 = 20.0;
+/// Time for an up-post to come all the way up or down.
+const UP_POST_TRAVEL_TIME: f32 = 0.1;
+
+/// Closes off the lock lane while energized.
+pub const LOCK_DIVERTER: &str = "Lock Diverter";
+/// Up-post between the flipper tips.
+pub const CENTER_POST: &str = "Center Post";
+
+/// Energizes or releases the coil of the device with this `Name`.
+#[derive(Message, Clone, Debug)]
+pub struct CoilCommand {
+    pub coil: String,
+    pub energized: bool,
+}
+
+#[derive(Component, Default)]
+pub(crate) struct Coil {
+    pub energized: bool,
+}
+
+/// A flap hinged at its origin, pointing along `rest_angle` or, while its
+/// coil is energized, `active_angle`.
+#[derive(Component)]
+struct Diverter {
+    rest_angle: f32,
+    active_angle: f32,
+}
+
+#[derive(Component)]
+struct UpPost {
+    /// 0 when down, 1 when up.
+    raised: f32,
+}
+
+#[derive(Component)]
+struct UpPostCap;
+
+fn spawn_coil_devices(mut commands: Commands) {
+    // Hinged on top of the lock lane guide. It stands up out of the way at rest,
+    // and swings down across the mouth of the lane to roll balls back off the
+    // guide onto the playfield instead of into the lock.
+    spawn_diverter(
+        &mut commands,
+        LOCK_DIVERTER,
+        Vec2::new(0.155, 0.1) * crate::PIXELS_PER_METER,
+        crate::PIXELS_PER_METER * 0.07,
+        90.0_f32.to_radians(),
+        15.0_f32.to_radians(),
+    );
+    // Between the flipper tips, to save the ball from the centre drain.
+    spawn_up_post(
+        &mut commands,
+        CENTER_POST,
+        Vec2::new(-0.05, -0.56) * crate::PIXELS_PER_METER,
+        crate::PIXELS_PER_METER * 0.012,
+    );
+}
+
+fn spawn_diverter(
+    commands: &mut Commands,
+    name: &str,
+    hinge: Vec2,
+    length: f32,
+    rest_angle: f32,
+    active_angle: f32,
+) {
+    let thickness = crate::PIXELS_PER_METER * 0.01;
+    let path = ShapePath::new()
+        .move_to(Vec2::ZERO)
+        .line_to(Vec2::X * length);
+    commands.spawn((
+        Name::from(name),
+        ShapeBuilder::with(&path)
+            .stroke((bevy::color::palettes::css::SILVER, thickness))
+            .build(),
+        RigidBody::Kinematic,
+        Collider::capsule_endpoints(thickness / 2.0, Vec2::ZERO, Vec2::X * length),
+        // Bodies turn about their centre of mass, which has to be the hinge, not the blade's middle.
+        CenterOfMass(Vec2::ZERO),
+        TableMaterial::Metal,
+        Transform::from_translation(hinge.extend(0.1))
+            .with_rotation(Quat::from_rotation_z(rest_angle)),
+        Coil::default(),
+        Diverter {
+            rest_angle,
+            active_angle,
+        },
+    ));
+}
+
+fn spawn_up_post(commands: &mut Commands, name: &str, position: Vec2, radius: f32) {
+    let shape_post = shapes::Circle {
+        radius,
+        center: Vec2::ZERO,
+    };
+    commands.spawn((
+        Name::from(name),
+        RigidBody::Static,
+        Collider::circle(radius),
+        // starts down
+        ColliderDisabled,
+        TableMaterial::Rubber,
+        Transform::from_translation(position.extend(0.1)),
+        Visibility::default(),
+        Coil::default(),
+        UpPost { raised: 0.0 },
+        children![(
+            Name::from("UpPostCap"),
+            ShapeBuilder::with(&shape_post)
+                .fill(Color::BLACK)
+                .stroke((bevy::color::palettes::css::TEAL, 2.0))
+                .build(),
+            Transform::from_scale(Vec3::ZERO),
+            UpPostCap,
+        )],
+    ));
+}
+
+fn handle_coil_commands(
+    mut coil_commands: MessageReader<CoilCommand>,
+    mut coils: Query<(&Name, &mut Coil)>,
+) {
+    for command in coil_commands.read() {
+        let Some((_, mut coil)) = coils
+            .iter_mut()
+            .find(|(name, _)| name.as_str() == command.coil)
+        else {
+            warn!("No coil named {}", command.coil);
+            continue;
+        };
+        coil.energized = command.energized;
+    }
+}
+
+fn swing_diverters(
+    time: Res<Time>,
+    mut diverters: Query<(&Coil, &Diverter, &Rotation, &mut AngularVelocity)>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+    for (coil, diverter, rotation, mut angular_velocity) in &mut diverters {
+        let target = if coil.energized {
+            diverter.active_angle
+        } else {
+            diverter.rest_angle
+        };
+        // the short way round
+        let error = Rot2::radians(target - rotation.as_radians()).as_radians();
+        angular_velocity.0 = (error / dt).clamp(-DIVERTER_SPEED, DIVERTER_SPEED);
+    }
+}
+
+fn move_up_posts(
+    time: Res<Time>,
+    mut posts: Query<(Entity, &Coil, &mut UpPost, &Children)>,
+    mut caps: Query<&mut Transform, With<UpPostCap>>,
+    mut commands: Commands,
+) {
+    let step = time.delta_secs() / UP_POST_TRAVEL_TIME;
+    for (entity, coil, mut post, children) in &mut posts {
+        let raised = if coil.energized {
+            (post.raised + step).min(1.0)
+        } else {
+            (post.raised - step).max(0.0)
+        };
+        if raised == post.raised {
+            continue;
+        }
+        // It only stops the ball once fully up, and lets go as soon as it starts down.
+        if raised == 1.0 {
+            commands.entity(entity).remove::<ColliderDisabled>();
+        } else if post.raised == 1.0 {
+            commands.entity(entity).insert(ColliderDisabled);
+        }
+        post.raised = raised;
+
+        for child in children.iter() {
+            if let Ok(mut transform) = caps.get_mut(child) {
+                transform.scale = Vec3::splat(raised);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn energize(sim: &mut Sim, coil: &str, energized: bool) {
+        sim.app.world_mut().write_message(CoilCommand {
+            coil: coil.to_string(),
+            energized,
+        });
+    }
+
+    /// Where the diverter's hinge end is, and its angle.
+    fn diverter_pose(sim: &mut Sim) -> (Vec2, f32) {
+        let world = sim.app.world_mut();
+        let mut diverters = world.query_filtered::<(&Position, &Rotation), With<Diverter>>();
+        let (position, rotation) = diverters.single(world).unwrap();
+        (position.0, rotation.as_radians())
+    }
+
+    /// Steps the sim, checking the diverter stays on its hinge throughout.
+    fn swing(sim: &mut Sim, ticks: usize) -> f32 {
+        let hinge = Vec2::new(0.155, 0.1) * crate::PIXELS_PER_METER;
+        for _ in 0..ticks {
+            sim.step(1);
+            let (position, _) = diverter_pose(sim);
+            assert!(position.distance(hinge) < 0.01, "hinge moved to {position}");
+        }
+        diverter_pose(sim).1
+    }
+
+    #[test]
+    fn diverter_swings_over_and_back() {
+        let mut sim = Sim::new();
+        energize(&mut sim, LOCK_DIVERTER, true);
+        let angle = swing(&mut sim, 16);
+        assert!((angle - 15.0_f32.to_radians()).abs() < 0.05);
+
+        energize(&mut sim, LOCK_DIVERTER, false);
+        let angle = swing(&mut sim, 16);
+        assert!((angle - 90.0_f32.to_radians()).abs() < 0.05);
+    }
+
+    /// Whether a ball dropped just off the centre line above the centre post
+    /// is still on the table a second later.
+    fn survives_drop_onto_center_post(raised: bool) -> bool {
+        let mut sim = Sim::new();
+        energize(&mut sim, CENTER_POST, raised);
+        sim.step(16);
+
+        let ball = sim.ball();
+        // off-centre, so it can't just balance on top of the post
+        let above_post = Vec2::new(-0.04, -0.45) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, above_post, Vec2::ZERO);
+        sim.step(64);
+        sim.ball_state(ball).is_some()
+    }
+
+    #[test]
+    fn raised_center_post_stops_a_draining_ball() {
+        assert!(
+            survives_drop_onto_center_post(true),
+            "ball drained past the raised post"
+        );
+        assert!(
+            !survives_drop_onto_center_post(false),
+            "ball stayed on the table with the post down"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Debug overlays, toggled at runtime.
+//!
+//! F1 shows Avian's collider/joint/contact gizmos, F2 the egui world inspector,
+//! F3 a diagnostics panel with frame rate and physics stepping info, alongside
+//! Avian's own physics timings and a game panel with the balls, score and rule
+//! timers, and F4 the tuning panel.
+
+use crate::ball::Ball;
+use crate::input::InputTick;
+use crate::rules::{RuleTimers, Score};
+use avian2d::diagnostics::ui::PhysicsDiagnosticsUiSettings;
+use avian2d::prelude::*;
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPlugin, EguiPrimaryContextPass, egui};
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlays>()
+            .add_plugins(EguiPlugin::default())
+            .add_plugins(
+                WorldInspectorPlugin::new()
+                    .run_if(|overlays: Res<DebugOverlays>| overlays.inspector),
+            )
+            .add_plugins(PhysicsDebugPlugin)
+            .add_plugins(FrameTimeDiagnosticsPlugin::default())
+            .add_plugins((PhysicsDiagnosticsPlugin, PhysicsDiagnosticsUiPlugin))
+            .add_systems(
+                Update,
+                (
+                    toggle_overlays,
+                    sync_overlays.run_if(resource_changed::<DebugOverlays>),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                (diagnostics_panel, game_panel)
+                    .run_if(|overlays: Res<DebugOverlays>| overlays.diagnostics),
+            );
+    }
+}
+
+/// Which overlays are showing.
+#[derive(Resource, Default)]
+pub struct DebugOverlays {
+    pub physics_gizmos: bool,
+    pub inspector: bool,
+    pub diagnostics: bool,
+    pub tuning: bool,
+}
+
+fn toggle_overlays(keyboard: Res<ButtonInput<KeyCode>>, mut overlays: ResMut<DebugOverlays>) {
+    if keyboard.just_pressed(KeyCode::F1) {
+        overlays.physics_gizmos = !overlays.physics_gizmos;
+    }
+    if keyboard.just_pressed(KeyCode::F2) {
+        overlays.inspector = !overlays.inspector;
+    }
+    if keyboard.just_pressed(KeyCode::F3) {
+        overlays.diagnostics = !overlays.diagnostics;
+    }
+    if keyboard.just_pressed(KeyCode::F4) {
+        overlays.tuning = !overlays.tuning;
+    }
+}
+
+fn sync_overlays(
+    overlays: Res<DebugOverlays>,
+    mut gizmos: ResMut<GizmoConfigStore>,
+    mut physics_ui: ResMut<PhysicsDiagnosticsUiSettings>,
+) {
+    gizmos.config_mut::<PhysicsGizmos>().0.enabled = overlays.physics_gizmos;
+    physics_ui.enabled = overlays.diagnostics;
+}
+
+fn diagnostics_panel(
+    mut contexts: EguiContexts,
+    diagnostics: Res<DiagnosticsStore>,
+    fixed_time: Res<Time<Fixed>>,
+    substeps: Res<SubstepCount>,
+    tick: Res<InputTick>,
+) -> Result {
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let frame_time = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+        .unwrap_or_default();
+
+    egui::Window::new("Diagnostics").show(contexts.ctx_mut()?, |ui| {
+        ui.label(format!("FPS: {fps:.0} ({frame_time:.1} ms)"));
+        ui.label(format!(
+            "Physics: {:.0} Hz x {} substeps",
+            fixed_time.timestep().as_secs_f64().recip(),
+            substeps.0
+        ));
+        ui.label(format!("Tick: {}", tick.0));
+    });
+    Ok(())
+}
+
+fn game_panel(
+    mut contexts: EguiContexts,
+    timers: Res<RuleTimers>,
+    score: Res<Score>,
+    balls: Query<(), With<Ball>>,
+) -> Result {
+    egui::Window::new("Game").show(contexts.ctx_mut()?, |ui| {
+        ui.label(format!("Balls: {}", balls.iter().count()));
+        ui.label(format!("Score: {}", score.0));
+        let window = |active: bool, timer: &Timer| {
+            if active {
+                format!("{:.1} s left", timer.remaining_secs())
+            } else {
+                "off".to_string()
+            }
+        };
+        ui.label(format!(
+            "Ball save: {}, skill shot: {}",
+            window(timers.ball_save_active(), &timers.ball_save),
+            window(timers.skill_shot_active(), &timers.skill_shot)
+        ));
+    });
+    Ok(())
+}
@@ -1,10 +1,10 @@
+use crate::input::TableInput;
+use crate::materials::{RestitutionScale, TableMaterial};
+use crate::table::TableConfig;
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 
-const FLIPPER_ENABLED_TORQUE: f32 = 1500000.0;
-const FLIPPER_DISABLED_TORQUE: f32 = -500000.0;
-
 // Typical pinball flipper extents involve a maximum upward swing of about 20 degrees for each flipper,
 // and a swing of 55-60 degrees from their resting position.
 const FLIPPER_MAX_UP_ANGLE: f32 = 20.0_f32.to_radians();
@@ -15,8 +15,8 @@ pub struct FlippersPlugin;
 impl Plugin for FlippersPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_flippers)
-            .add_systems(Update, left_flipper_movement)
-            .add_systems(Update, right_flipper_movement);
+            .add_systems(FixedUpdate, left_flipper_movement)
+            .add_systems(FixedUpdate, right_flipper_movement);
     }
 }
 
@@ -28,13 +28,14 @@ struct RightFlipper;
 
 fn spawn_flippers(mut commands: Commands) {
     //Spawn flippers
+    let extents = Vec2::new(
+        crate::PIXELS_PER_METER * 0.25,
+        crate::PIXELS_PER_METER * 0.05,
+    );
     let shape_flipper = shapes::Rectangle {
-        extents: Vec2::new(
-            crate::PIXELS_PER_METER * 0.25,
-            crate::PIXELS_PER_METER * 0.05,
-        ),
+        extents,
         origin: shapes::RectangleOrigin::Center,
-        radii: None,
+        radii: Some(BorderRadii::single(extents.y / 2.0)),
     };
 
     //Spawn left flipper
@@ -73,11 +74,13 @@ fn spawn_flippers(mut commands: Commands) {
                 .stroke((bevy::color::palettes::css::TEAL, 2.0))
                 .build(),
             RigidBody::Dynamic,
-            Collider::rectangle(shape_flipper.extents.x, shape_flipper.extents.y),
+            flipper_collider(shape_flipper.extents),
             //SleepingDisabled,
             Mass::from(1.0),
             // flippers have rubbers that make them bouncy
-            Restitution::from(0.4),
+            TableMaterial::Rubber,
+            // set every tick from the input
+            ConstantTorque(0.0),
             Transform::from_xyz(left_flipper_pos.x, left_flipper_pos.y, 0.0),
             LeftFlipper,
         ))
@@ -96,6 +99,41 @@ fn spawn_flippers(mut commands: Commands) {
         crate::PIXELS_PER_METER * 0.1,
         crate::PIXELS_PER_METER * -0.4,
     );
+    spawn_right_flipper(
+        &mut commands,
+        "Right",
+        right_flipper_pos.extend(0.0),
+        shape_flipper.extents,
+        1.0,
+    );
+}
+
+/// A flipper with round ends. The pivot is at the centre of one of them, so
+/// the back of the flipper stays put as it swings instead of lifting a ball
+/// resting on it.
+fn flipper_collider(extents: Vec2) -> Collider {
+    let half_length = (extents.x - extents.y) / 2.0;
+    Collider::capsule_endpoints(
+        extents.y / 2.0,
+        Vec2::new(-half_length, 0.0),
+        Vec2::new(half_length, 0.0),
+    )
+}
+
+/// Spawns a right flipper centred on `position`, hinged at its right end and
+/// driven by the right flipper button. Returns the flipper.
+pub(crate) fn spawn_right_flipper(
+    commands: &mut Commands,
+    name: &str,
+    position: Vec3,
+    extents: Vec2,
+    mass: f32,
+) -> Entity {
+    let shape_flipper = shapes::Rectangle {
+        extents,
+        origin: shapes::RectangleOrigin::Center,
+        radii: Some(BorderRadii::single(extents.y / 2.0)),
+    };
     let right_pivot = Vec2::new(
         shape_flipper.extents.x / 2.0 - shape_flipper.extents.y / 2.0,
         0.0,
@@ -103,7 +141,7 @@ fn spawn_flippers(mut commands: Commands) {
 
     let right_anchor = commands
         .spawn((
-            Name::from("Right Flipper Anchor"),
+            Name::from(format!("{name} Flipper Anchor")),
             // ShapeBuilder::with(&shapes::Circle {
             //     radius: 5.0,
             //     center: Vec2::ZERO,
@@ -112,27 +150,29 @@ fn spawn_flippers(mut commands: Commands) {
             // .build(),
             RigidBody::Static,
             Transform::from_xyz(
-                right_flipper_pos.x + right_pivot.x,
-                right_flipper_pos.y + right_pivot.y,
-                0.1,
+                position.x + right_pivot.x,
+                position.y + right_pivot.y,
+                position.z + 0.1,
             ),
         ))
         .id();
 
     let right_flipper = commands
         .spawn((
-            Name::from("Flipper Right"),
+            Name::from(format!("Flipper {name}")),
             ShapeBuilder::with(&shape_flipper)
                 .fill(Color::BLACK)
                 .stroke((bevy::color::palettes::css::TEAL, 2.0))
                 .build(),
             RigidBody::Dynamic,
-            Collider::rectangle(shape_flipper.extents.x, shape_flipper.extents.y),
+            flipper_collider(shape_flipper.extents),
             //SleepingDisabled,
-            Mass::from(1.0),
-            // flippers have rubbers that make them bouncy
-            Restitution::from(0.5),
-            Transform::from_xyz(right_flipper_pos.x, right_flipper_pos.y, 0.0),
+            Mass::from(mass),
+            // flippers have rubbers that make them bouncy, livelier on this side
+            TableMaterial::Rubber,
+            RestitutionScale(1.25),
+            ConstantTorque(0.0),
+            Transform::from_translation(position),
             RightFlipper,
         ))
         .id();
@@ -141,7 +181,7 @@ fn spawn_flippers(mut commands: Commands) {
     //   You can override the center of mass and set rotation speed directly on a kinematic body
 
     commands.spawn((
-        Name::from("Right Flipper Joint"),
+        Name::from(format!("{name} Flipper Joint")),
         RevoluteJoint::new(right_anchor, right_flipper)
             .with_local_anchor1(Vec2::ZERO)
             .with_local_anchor2(right_pivot)
@@ -152,47 +192,269 @@ fn spawn_flippers(mut commands: Commands) {
         //     ..default()
         // },
     ));
+
+    right_flipper
 }
 
 fn left_flipper_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut flippers: Query<Entity, With<LeftFlipper>>,
-    mut commands: Commands,
+    input: Res<TableInput>,
+    config: Res<TableConfig>,
+    mut flippers: Query<&mut ConstantTorque, With<LeftFlipper>>,
 ) {
-    for flipper in flippers.iter_mut() {
-        if keyboard_input.pressed(KeyCode::ArrowLeft) || keyboard_input.pressed(KeyCode::ShiftLeft)
-        {
-            commands
-                .entity(flipper)
-                .insert(ConstantTorque(FLIPPER_ENABLED_TORQUE));
+    for mut torque in flippers.iter_mut() {
+        torque.0 = if input.left_flipper {
+            config.flipper_torque
         } else {
-            // since gravity is not pulling enough we force a torque in the opposite direction
-            //commands.entity(flipper).remove::<ConstantTorque>();
-            commands
-                .entity(flipper)
-                .insert(ConstantTorque(FLIPPER_DISABLED_TORQUE));
-        }
+            -config.flipper_return_torque
+        };
     }
 }
 
 fn right_flipper_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut flippers: Query<Entity, With<RightFlipper>>,
-    mut commands: Commands,
+    input: Res<TableInput>,
+    config: Res<TableConfig>,
+    mut flippers: Query<&mut ConstantTorque, With<RightFlipper>>,
 ) {
-    for flipper in flippers.iter_mut() {
-        if keyboard_input.pressed(KeyCode::ArrowRight)
-            || keyboard_input.pressed(KeyCode::ShiftRight)
-        {
-            commands
-                .entity(flipper)
-                .insert(ConstantTorque(-FLIPPER_ENABLED_TORQUE));
+    for mut torque in flippers.iter_mut() {
+        torque.0 = if input.right_flipper {
+            -config.flipper_torque
         } else {
-            // since gravity is not pulling enough we force a torque in the opposite direction
-            //commands.entity(flipper).remove::<ConstantTorque>();
-            commands
-                .entity(flipper)
-                .insert(ConstantTorque(-FLIPPER_DISABLED_TORQUE));
+            config.flipper_return_torque
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{ScriptedInput, Sim};
+
+    const LEFT: KeyCode = KeyCode::ArrowLeft;
+    const RIGHT: KeyCode = KeyCode::ArrowRight;
+
+    /// Ticks needed for a dropped ball to come to rest.
+    const SETTLE_TICKS: usize = 192;
+
+    fn left_pivot() -> Vec2 {
+        Vec2::new(-0.3, -0.4) * crate::PIXELS_PER_METER
+    }
+
+    fn right_pivot() -> Vec2 {
+        Vec2::new(0.2, -0.4) * crate::PIXELS_PER_METER
+    }
+
+    fn is_cradled_left(sim: &mut Sim, ball: Entity) -> bool {
+        sim.rest_position(ball).is_some_and(|position| {
+            position.x < left_pivot().x + 30.0 && position.y > left_pivot().y - 10.0
+        })
+    }
+
+    fn is_cradled_right(sim: &mut Sim, ball: Entity) -> bool {
+        sim.rest_position(ball).is_some_and(|position| {
+            position.x > right_pivot().x - 30.0 && position.y > right_pivot().y - 10.0
+        })
+    }
+
+    /// Drops the served ball onto the raised left flipper and waits for it to settle.
+    fn cradled_on_left(hold_right: bool) -> (Sim, Entity) {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        sim.press(LEFT);
+        if hold_right {
+            sim.press(RIGHT);
         }
+        sim.step(10);
+        sim.place_ball(ball, Vec2::new(-90.0, -100.0), Vec2::ZERO);
+        sim.step(SETTLE_TICKS);
+        (sim, ball)
+    }
+
+    /// Drops the raised left flipper, waits for the ball rolling down it to
+    /// reach `tap_x`, then flips for `tap_ticks` and lets the ball settle. A ball
+    /// passed across rolls slowly down the other flipper, so this allows twice
+    /// the usual time.
+    fn flip_rolling_ball(sim: &mut Sim, ball: Entity, tap_x: f32, tap_ticks: usize) {
+        sim.release(LEFT);
+        while sim
+            .ball_state(ball)
+            .is_some_and(|(position, _)| position.x < tap_x)
+        {
+            sim.step(1);
+        }
+        sim.play(
+            &[
+                ScriptedInput::press(0, LEFT),
+                ScriptedInput::release(tap_ticks, LEFT),
+            ],
+            tap_ticks + 2 * SETTLE_TICKS,
+        );
+    }
+
+    /// Drops a ball from `start` and returns the tick at which it lands on the flipper.
+    fn ticks_until_landing(sim: &mut Sim, ball: Entity, start: Vec2) -> usize {
+        sim.place_ball(ball, start, Vec2::ZERO);
+        let mut fall_speed = 0.0;
+        for ticks in 1..=SETTLE_TICKS {
+            sim.step(1);
+            let Some((_, velocity)) = sim.ball_state(ball) else {
+                break;
+            };
+            // Gravity only ever speeds the fall up; the flipper is the first thing to slow it.
+            if -velocity.y < fall_speed {
+                return ticks;
+            }
+            fall_speed = -velocity.y;
+        }
+        panic!("ball never reached the flipper");
+    }
+
+    /// Highest point the ball reaches within half a second from now.
+    fn rebound_height(sim: &mut Sim, ball: Entity) -> f32 {
+        let mut highest = f32::MIN;
+        for _ in 0..32 {
+            sim.step(1);
+            if let Some((position, _)) = sim.ball_state(ball) {
+                highest = highest.max(position.y);
+            }
+        }
+        highest
+    }
+
+    #[test]
+    fn cradle_hold_brings_ball_to_rest_on_raised_flipper() {
+        let (mut sim, ball) = cradled_on_left(false);
+        assert!(
+            is_cradled_left(&mut sim, ball),
+            "ball not cradled: {:?}",
+            sim.ball_state(ball)
+        );
+    }
+
+    #[test]
+    fn cradle_hold_works_on_right_flipper() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        sim.press(RIGHT);
+        sim.step(10);
+        sim.place_ball(ball, Vec2::new(50.0, -100.0), Vec2::ZERO);
+        sim.step(SETTLE_TICKS);
+        assert!(
+            is_cradled_right(&mut sim, ball),
+            "ball not cradled: {:?}",
+            sim.ball_state(ball)
+        );
+    }
+
+    #[test]
+    fn drop_catch_deadens_the_bounce() {
+        let start = Vec2::new(-70.0, -20.0);
+
+        let mut held = Sim::new();
+        let ball = held.ball();
+        held.press(LEFT);
+        held.step(10);
+        let impact = ticks_until_landing(&mut held, ball, start);
+        let held_rebound = rebound_height(&mut held, ball);
+
+        // Drop the flipper just before the ball lands, like a player letting go on impact.
+        let dropped_rebound = (1..=3)
+            .map(|early| {
+                let mut sim = Sim::new();
+                let ball = sim.ball();
+                sim.press(LEFT);
+                sim.step(10);
+                sim.place_ball(ball, start, Vec2::ZERO);
+                sim.step(impact - early);
+                sim.release(LEFT);
+                rebound_height(&mut sim, ball)
+            })
+            .fold(f32::MAX, f32::min);
+
+        assert!(
+            dropped_rebound < held_rebound,
+            "dropping the flipper should absorb the bounce: {dropped_rebound} vs {held_rebound}"
+        );
+    }
+
+    #[test]
+    fn dead_flipper_lets_dropped_ball_drain() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        sim.place_ball(ball, Vec2::new(-70.0, -50.0), Vec2::ZERO);
+        sim.step(SETTLE_TICKS);
+        assert!(sim.ball_state(ball).is_none(), "ball should have drained");
+    }
+
+    #[test]
+    fn live_catch_stops_a_falling_ball() {
+        let start = Vec2::new(-70.0, -50.0);
+        // The flipper meets the ball on its way up, so time the flip against the raised flipper.
+        let mut probe = Sim::new();
+        let ball = probe.ball();
+        probe.press(LEFT);
+        probe.step(10);
+        let impact = ticks_until_landing(&mut probe, ball, start);
+
+        // The flipper needs about four ticks to swing up; try flipping from just before to
+        // just after the ball gets there.
+        let caught = (impact.saturating_sub(4)..impact + 6).any(|flip_at| {
+            let mut sim = Sim::new();
+            let ball = sim.ball();
+            sim.place_ball(ball, start, Vec2::ZERO);
+            sim.play(
+                &[ScriptedInput::press(flip_at, LEFT)],
+                flip_at + SETTLE_TICKS,
+            );
+            is_cradled_left(&mut sim, ball)
+        });
+        assert!(caught, "no flip timing caught the ball");
+    }
+
+    #[test]
+    fn tap_pass_moves_cradled_ball_to_other_flipper() {
+        let passed = [-130.0, -120.0, -110.0].into_iter().any(|tap_x| {
+            (1..4).any(|tap_ticks| {
+                let (mut sim, ball) = cradled_on_left(true);
+                flip_rolling_ball(&mut sim, ball, tap_x, tap_ticks);
+                is_cradled_right(&mut sim, ball)
+            })
+        });
+        assert!(passed, "no tap passed the ball to the right flipper");
+    }
+
+    #[test]
+    fn cradle_separation_shoots_only_the_front_ball() {
+        // The front ball has to roll out towards the tip before the flip, so it can
+        // take a while.
+        let separated = (1..30).any(|drop_ticks| {
+            let (mut sim, back) = cradled_on_left(false);
+            let front = sim.spawn_ball();
+            sim.place_ball(front, Vec2::new(-70.0, -100.0), Vec2::ZERO);
+            sim.step(SETTLE_TICKS);
+
+            sim.release(LEFT);
+            sim.step(drop_ticks);
+            sim.press(LEFT);
+            let mut front_height = f32::MIN;
+            for _ in 0..60 {
+                sim.step(1);
+                if let Some((position, _)) = sim.ball_state(front) {
+                    front_height = front_height.max(position.y);
+                }
+            }
+            // The back ball, by the pivot, barely moves and has to stay in the cradle.
+            sim.step(SETTLE_TICKS);
+            front_height > left_pivot().y + 200.0 && is_cradled_left(&mut sim, back)
+        });
+        assert!(separated, "no flip timing separated the two cradled balls");
+    }
+
+    #[test]
+    fn post_pass_bounces_ball_to_other_flipper() {
+        // Flipped late, from near the tip, the ball goes across into the right
+        // slingshot's post and drops onto the right flipper.
+        let (mut sim, ball) = cradled_on_left(true);
+        flip_rolling_ball(&mut sim, ball, -100.0, 2);
+        assert!(is_cradled_right(&mut sim, ball));
     }
 }
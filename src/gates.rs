@@ -0,0 +1,161 @@
+//! One-way gates.
+//!
+//! A gate is a light plate hanging from a hinge on a `RevoluteJoint`. The
+//! joint's angle limits stop it at the closed position on one side, so a ball
+//! coming from that side is turned away, while a ball from the other side
+//! swings it open and passes. The playfield slope swings it shut again.
+
+use crate::materials::TableMaterial;
+use crate::shooter_lane::ShooterLaneGate;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct GatesPlugin;
+
+impl Plugin for GatesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_gates);
+    }
+}
+
+const GATE_THICKNESS: f32 = crate::PIXELS_PER_METER * 0.008;
+
+/// On a gate's joint; a latched gate is held shut against balls from either side.
+#[derive(Component)]
+pub(crate) struct Gate {
+    swing: f32,
+}
+
+impl Gate {
+    /// Holds the gate on `joint` shut, whichever way it could swing.
+    pub fn latch(&self, joint: &mut RevoluteJoint) {
+        joint.angle_limit = Some(AngleLimit::new(0.0, 0.0));
+    }
+
+    /// Lets the gate on `joint` swing open one way again.
+    pub fn release(&self, joint: &mut RevoluteJoint) {
+        joint.angle_limit = Some(AngleLimit::new(self.swing.min(0.0), self.swing.max(0.0)));
+    }
+}
+
+fn spawn_gates(mut commands: Commands) {
+    // Top of the shooter lane: opens upwards for a launched ball, and tips a ball
+    // coming down the right orbit out onto the playfield instead of into the lane.
+    let gate = spawn_gate(
+        &mut commands,
+        "Shooter Lane Gate",
+        Vec2::new(
+            crate::PIXELS_PER_METER * 0.335,
+            crate::PIXELS_PER_METER * 0.21,
+        ),
+        crate::PIXELS_PER_METER * 0.075,
+        195.0_f32.to_radians(),
+        -85.0_f32.to_radians(),
+    );
+    commands.entity(gate).insert(ShooterLaneGate);
+}
+
+/// Spawns a gate hinged at `hinge`, pointing along `closed_angle` while shut.
+/// It can swing by up to `swing` radians (counter-clockwise if positive) from
+/// there, and not at all the other way. Returns the joint, which carries the [`Gate`].
+pub(crate) fn spawn_gate(
+    commands: &mut Commands,
+    name: &str,
+    hinge: Vec2,
+    length: f32,
+    closed_angle: f32,
+    swing: f32,
+) -> Entity {
+    let shape_gate = shapes::Rectangle {
+        extents: Vec2::new(length, GATE_THICKNESS),
+        origin: shapes::RectangleOrigin::Center,
+        radii: None,
+    };
+    let closed = Quat::from_rotation_z(closed_angle);
+    let center = hinge + Vec2::from_angle(closed_angle) * length / 2.0;
+
+    // The hinge is turned to the closed position, so the joint angle is zero when shut.
+    let anchor = commands
+        .spawn((
+            Name::from(format!("{name} Hinge")),
+            RigidBody::Static,
+            Transform::from_xyz(hinge.x, hinge.y, 0.1).with_rotation(closed),
+        ))
+        .id();
+
+    let gate = commands
+        .spawn((
+            Name::from(name.to_string()),
+            ShapeBuilder::with(&shape_gate)
+                .fill(bevy::color::palettes::css::SILVER)
+                .build(),
+            RigidBody::Dynamic,
+            Collider::rectangle(shape_gate.extents.x, shape_gate.extents.y),
+            TableMaterial::Metal,
+            // light, so the ball barely slows down pushing through
+            Mass::from(0.01),
+            AngularDamping(2.0),
+            Transform::from_xyz(center.x, center.y, 0.0).with_rotation(closed),
+        ))
+        .id();
+
+    commands
+        .spawn((
+            Name::from(format!("{name} Joint")),
+            RevoluteJoint::new(anchor, gate)
+                .with_local_anchor1(Vec2::ZERO)
+                .with_local_anchor2(Vec2::new(-length / 2.0, 0.0))
+                .with_angle_limits(swing.min(0.0), swing.max(0.0)),
+            Gate { swing },
+        ))
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::harness::Sim;
+    use bevy::prelude::*;
+
+    const GATE_Y: f32 = crate::PIXELS_PER_METER * 0.21;
+
+    #[test]
+    fn launched_ball_swings_the_gate_open() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let lane = Vec2::new(crate::PIXELS_PER_METER * 0.3, 0.0);
+        sim.place_ball(ball, lane, Vec2::new(0.0, 1500.0));
+
+        let mut highest = f32::MIN;
+        for _ in 0..40 {
+            sim.step(1);
+            let (position, _) = sim.ball_state(ball).unwrap();
+            highest = highest.max(position.y);
+        }
+        assert!(highest > GATE_Y + 50.0, "ball stopped at {highest}");
+    }
+
+    #[test]
+    fn ball_coming_down_the_orbit_is_kept_out_of_the_shooter_lane() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let orbit = Vec2::new(
+            crate::PIXELS_PER_METER * 0.3,
+            crate::PIXELS_PER_METER * 0.32,
+        );
+        sim.place_ball(ball, orbit, Vec2::new(0.0, -300.0));
+
+        for _ in 0..180 {
+            sim.step(1);
+            let Some((position, _)) = sim.ball_state(ball) else {
+                return;
+            };
+            assert!(
+                position.x < crate::PIXELS_PER_METER * 0.26 || position.y > GATE_Y - 20.0,
+                "ball got into the shooter lane at {position}"
+            );
+        }
+        let (position, _) = sim.ball_state(ball).unwrap();
+        assert!(position.x < crate::PIXELS_PER_METER * 0.25);
+    }
+}
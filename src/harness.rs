@@ -0,0 +1,164 @@
+//! Headless table for physics tests.
+//!
+//! Runs the table on `MinimalPlugins` via [`crate::headless_app`], so no window
+//! or GPU is needed.
+//! Time is stepped by hand: every [`Sim::step`] advances exactly one fixed
+//! physics tick, and inputs are sent as keyboard messages, either one by one
+//! or from a tick-stamped [`ScriptedInput`] list.
+
+use crate::ball::{Ball, spawn_ball};
+use crate::rolling::Roll;
+use avian2d::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey};
+use bevy::prelude::*;
+
+/// One key change, applied right before the given tick runs.
+#[derive(Clone, Copy, Debug)]
+pub struct ScriptedInput {
+    pub tick: usize,
+    pub key: KeyCode,
+    pub pressed: bool,
+}
+
+impl ScriptedInput {
+    pub fn press(tick: usize, key: KeyCode) -> Self {
+        Self {
+            tick,
+            key,
+            pressed: true,
+        }
+    }
+
+    pub fn release(tick: usize, key: KeyCode) -> Self {
+        Self {
+            tick,
+            key,
+            pressed: false,
+        }
+    }
+}
+
+pub struct Sim {
+    pub app: App,
+}
+
+impl Sim {
+    /// A freshly served table, after `Startup` has run.
+    pub fn new() -> Self {
+        Self::configured(|_| {})
+    }
+
+    /// Like [`Sim::new`], letting `configure` add resources before `Startup` runs.
+    pub fn configured(configure: impl FnOnce(&mut App)) -> Self {
+        let mut app = crate::headless_app();
+        configure(&mut app);
+        app.finish();
+        app.cleanup();
+        app.update();
+        Self { app }
+    }
+
+    pub fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    /// Runs `ticks` ticks, applying every scripted input whose tick falls in that range.
+    /// Script ticks are relative to the start of this call.
+    pub fn play(&mut self, script: &[ScriptedInput], ticks: usize) {
+        for tick in 0..ticks {
+            self.apply_inputs(script, tick);
+            self.step(1);
+        }
+    }
+
+    /// Sends the scripted inputs stamped with `tick`.
+    pub fn apply_inputs(&mut self, script: &[ScriptedInput], tick: usize) {
+        for input in script.iter().filter(|input| input.tick == tick) {
+            if input.pressed {
+                self.press(input.key);
+            } else {
+                self.release(input.key);
+            }
+        }
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    /// Goes through the regular keyboard messages so `just_pressed` works like
+    /// it does with a real keyboard.
+    fn send_key(&mut self, key_code: KeyCode, state: ButtonState) {
+        self.app.world_mut().write_message(KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    pub fn balls(&mut self) -> Vec<Entity> {
+        let world = self.app.world_mut();
+        world
+            .query_filtered::<Entity, With<Ball>>()
+            .iter(world)
+            .collect()
+    }
+
+    /// The ball served at startup.
+    pub fn ball(&mut self) -> Entity {
+        self.balls()[0]
+    }
+
+    /// Serves an extra ball and returns it.
+    pub fn spawn_ball(&mut self) -> Entity {
+        let before = self.balls();
+        self.app.world_mut().run_system_once(spawn_ball).unwrap();
+        self.balls()
+            .into_iter()
+            .find(|ball| !before.contains(ball))
+            .unwrap()
+    }
+
+    /// Teleports a ball to `position` and sets it rolling at `velocity`.
+    pub fn place_ball(&mut self, ball: Entity, position: Vec2, velocity: Vec2) {
+        let mut entity = self.app.world_mut().entity_mut(ball);
+        entity.get_mut::<Transform>().unwrap().translation = position.extend(0.0);
+        entity.insert((
+            Position(position),
+            LinearVelocity(velocity),
+            AngularVelocity::ZERO,
+            Roll::rolling(velocity),
+        ));
+    }
+
+    /// Steps a few ticks and returns where the ball ended up if it stayed put
+    /// meanwhile, or `None` if it moved or drained. A ball wedged against a
+    /// flipper or a guide rests in place while the contact solver still leaves
+    /// it some velocity, so this goes by position instead.
+    pub fn rest_position(&mut self, ball: Entity) -> Option<Vec2> {
+        let (start, _) = self.ball_state(ball)?;
+        self.step(8);
+        let (end, _) = self.ball_state(ball)?;
+        (start.distance(end) < 1.0).then_some(end)
+    }
+
+    /// Position and velocity of a ball, or `None` once it has drained.
+    pub fn ball_state(&self, ball: Entity) -> Option<(Vec2, Vec2)> {
+        let entity = self.app.world().get_entity(ball).ok()?;
+        Some((
+            entity.get::<Position>()?.0,
+            entity.get::<LinearVelocity>()?.0,
+        ))
+    }
+}
@@ -0,0 +1,119 @@
+//! Player input, latched once per fixed tick.
+//!
+//! The keyboard is sampled every render frame into a pending input, which is
+//! latched into [`TableInput`] at the start of each fixed tick. Flippers,
+//! plunger and nudge only read [`TableInput`] from `FixedUpdate`, so the game
+//! depends on the tick-stamped input stream rather than on the frame rate.
+//! Nudges are taps: each one is handed to exactly one tick, even if a frame
+//! runs several fixed ticks or none at all.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct TableInputPlugin;
+
+impl Plugin for TableInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TableInput>()
+            .init_resource::<PendingInput>()
+            .init_resource::<InputTick>()
+            .add_systems(
+                RunFixedMainLoop,
+                sample_keyboard.in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop),
+            )
+            .add_systems(FixedPreUpdate, latch_input)
+            .add_systems(FixedLast, advance_tick);
+    }
+}
+
+/// What the player is doing during the current fixed tick.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableInput {
+    pub left_flipper: bool,
+    pub right_flipper: bool,
+    pub plunger: bool,
+    /// Direction the ball should lurch from nudges tapped this tick, zero if none.
+    pub nudge: Vec2,
+}
+
+/// Index of the current fixed tick, counted from the first one.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct InputTick(pub u64);
+
+/// Keyboard state gathered since the last tick; nudges accumulate until latched.
+#[derive(Resource, Default)]
+pub(crate) struct PendingInput(TableInput);
+
+fn sample_keyboard(keyboard: Res<ButtonInput<KeyCode>>, mut pending: ResMut<PendingInput>) {
+    let pending = &mut pending.0;
+    pending.left_flipper =
+        keyboard.pressed(KeyCode::ArrowLeft) || keyboard.pressed(KeyCode::ShiftLeft);
+    pending.right_flipper =
+        keyboard.pressed(KeyCode::ArrowRight) || keyboard.pressed(KeyCode::ShiftRight);
+    pending.plunger = keyboard.pressed(KeyCode::Enter);
+
+    // Keys mirror Visual Pinball defaults: Z = left, / = right, Space = center.
+    // Flip a sign here if a direction feels backwards.
+    if keyboard.just_pressed(KeyCode::KeyZ) {
+        pending.nudge.x -= 1.0; // left nudge
+    }
+    if keyboard.just_pressed(KeyCode::Slash) {
+        pending.nudge.x += 1.0; // right nudge
+    }
+    if keyboard.just_pressed(KeyCode::Space) {
+        pending.nudge.y -= 1.0; // center nudge: jolt the table up (ball lurches down)
+    }
+}
+
+pub(crate) fn latch_input(mut pending: ResMut<PendingInput>, mut input: ResMut<TableInput>) {
+    *input = pending.0;
+    pending.0.nudge = Vec2::ZERO;
+}
+
+fn advance_tick(mut tick: ResMut<InputTick>) {
+    tick.0 += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::harness::{ScriptedInput, Sim};
+    use bevy::prelude::*;
+
+    /// A launch, a few flips and nudges, long enough for the ball to reach the flippers.
+    fn session() -> Vec<ScriptedInput> {
+        vec![
+            ScriptedInput::press(30, KeyCode::Enter),
+            ScriptedInput::release(90, KeyCode::Enter),
+            ScriptedInput::press(200, KeyCode::KeyZ),
+            ScriptedInput::release(202, KeyCode::KeyZ),
+            ScriptedInput::press(260, KeyCode::ArrowLeft),
+            ScriptedInput::release(290, KeyCode::ArrowLeft),
+            ScriptedInput::press(300, KeyCode::ShiftRight),
+            ScriptedInput::press(305, KeyCode::Space),
+            ScriptedInput::release(306, KeyCode::Space),
+            ScriptedInput::release(340, KeyCode::ShiftRight),
+        ]
+    }
+
+    /// Every ball's position and velocity after each tick of `script`.
+    fn trajectory(script: &[ScriptedInput], ticks: usize) -> Vec<Vec<(Vec2, Vec2)>> {
+        let mut sim = Sim::new();
+        (0..ticks)
+            .map(|tick| {
+                sim.apply_inputs(script, tick);
+                sim.step(1);
+                sim.balls()
+                    .into_iter()
+                    .filter_map(|ball| sim.ball_state(ball))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_inputs_replay_identically() {
+        let first = trajectory(&session(), 640);
+        let second = trajectory(&session(), 640);
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,171 @@
+//! Left outlane kickback.
+//!
+//! A sensor low in the left outlane. While lit, a ball draining past it is
+//! kicked straight back up the outlane, and the kickback goes out until a
+//! rule relights it with [`LightKickback`].
+
+use crate::ball::Ball;
+use crate::lamps::{LampCommand, LampState, lamp};
+use crate::table::TableConfig;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct KickbackPlugin;
+
+impl Plugin for KickbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<LightKickback>()
+            .add_systems(Startup, spawn_kickback)
+            .add_systems(
+                FixedUpdate,
+                (light_kickback, fire_kickback, show_kickback_lit).chain(),
+            );
+    }
+}
+
+/// Speed a kicked ball leaves with, enough to get it back out of the outlane.
+const KICKBACK_SPEED: f32 = crate::PIXELS_PER_METER * 2.2;
+
+/// Lights the kickback.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct LightKickback;
+
+#[derive(Component)]
+pub(crate) struct Kickback {
+    pub lit: bool,
+}
+
+fn spawn_kickback(mut commands: Commands) {
+    let shape_kickback = shapes::Rectangle {
+        extents: Vec2::new(
+            crate::PIXELS_PER_METER * 0.06,
+            crate::PIXELS_PER_METER * 0.02,
+        ),
+        origin: shapes::RectangleOrigin::Center,
+        radii: None,
+    };
+
+    commands.spawn((
+        Name::from("Left Kickback"),
+        ShapeBuilder::with(&shape_kickback)
+            .stroke((bevy::color::palettes::css::TEAL, 1.0))
+            .build(),
+        RigidBody::Static,
+        Collider::rectangle(shape_kickback.extents.x, shape_kickback.extents.y),
+        Sensor,
+        Transform::from_xyz(
+            crate::PIXELS_PER_METER * -0.385,
+            crate::PIXELS_PER_METER * -0.45,
+            0.0,
+        ),
+        // lit for the first ball
+        Kickback { lit: true },
+        children![lamp(
+            "Left Kickback Lamp",
+            Vec2::new(0.0, crate::PIXELS_PER_METER * 0.06),
+            crate::PIXELS_PER_METER * 0.012,
+            bevy::color::palettes::css::ORANGE.into(),
+            1.0,
+            LampState::On,
+        )],
+    ));
+}
+
+fn light_kickback(mut light: MessageReader<LightKickback>, mut kickbacks: Query<&mut Kickback>) {
+    if light.read().count() == 0 {
+        return;
+    }
+    for mut kickback in &mut kickbacks {
+        kickback.lit = true;
+    }
+}
+
+fn fire_kickback(
+    mut collision_reader: MessageReader<CollisionStart>,
+    mut kickbacks: Query<&mut Kickback>,
+    mut balls: Query<Forces, With<Ball>>,
+    config: Res<TableConfig>,
+) {
+    for event in collision_reader.read() {
+        let (Some(body1), Some(body2)) = (event.body1, event.body2) else {
+            continue;
+        };
+        let (kickback, ball) = if kickbacks.contains(body1) {
+            (body1, body2)
+        } else if kickbacks.contains(body2) {
+            (body2, body1)
+        } else {
+            continue;
+        };
+        let (Ok(mut kickback), Ok(mut forces)) = (kickbacks.get_mut(kickback), balls.get_mut(ball))
+        else {
+            continue;
+        };
+        if !kickback.lit {
+            continue;
+        }
+        // Whatever way the ball was going, it leaves straight up.
+        let velocity = forces.linear_velocity();
+        forces.apply_linear_impulse((Vec2::new(0.0, KICKBACK_SPEED) - velocity) * config.ball_mass);
+        kickback.lit = false;
+    }
+}
+
+fn show_kickback_lit(
+    kickbacks: Query<(&Name, &Kickback), Changed<Kickback>>,
+    mut lamps: MessageWriter<LampCommand>,
+) {
+    for (name, kickback) in &kickbacks {
+        let state = if kickback.lit {
+            LampState::On
+        } else {
+            LampState::Off
+        };
+        lamps.write(LampCommand::new(format!("{name} Lamp"), state));
+    }
+}
+
+/// Whether the outlane kickback is lit.
+#[cfg(test)]
+pub(crate) fn kickback_lit(world: &mut World) -> bool {
+    world.query::<&Kickback>().single(world).unwrap().lit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn drop_into_outlane(sim: &mut Sim) -> Entity {
+        let ball = sim.ball();
+        let outlane = Vec2::new(-0.385, -0.3) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, outlane, Vec2::new(0.0, -200.0));
+        ball
+    }
+
+    #[test]
+    fn lit_kickback_returns_the_ball_once() {
+        let mut sim = Sim::new();
+        let ball = drop_into_outlane(&mut sim);
+        sim.step(32);
+        let (position, velocity) = sim.ball_state(ball).expect("ball drained");
+        assert!(velocity.y > 0.0, "ball at {position} not kicked back");
+
+        let ball = drop_into_outlane(&mut sim);
+        sim.step(64);
+        assert!(sim.ball_state(ball).is_none(), "unlit kickback fired");
+    }
+
+    #[test]
+    fn rule_relights_the_kickback() {
+        let mut sim = Sim::new();
+        drop_into_outlane(&mut sim);
+        sim.step(32);
+        assert!(!kickback_lit(sim.app.world_mut()));
+
+        sim.app.world_mut().write_message(LightKickback);
+        sim.step(1);
+        assert!(kickback_lit(sim.app.world_mut()));
+    }
+}
@@ -0,0 +1,267 @@
+//! Lamps.
+//!
+//! Every light on the table, from the bumper caps and inserts to the general
+//! illumination, is a named [`Lamp`]. Rules and light shows never touch a lamp
+//! directly; they send a [`LampCommand`] with its name. Lamp states advance on
+//! the fixed tick like the rules do, so blinking stays in step with the game.
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct LampsPlugin;
+
+impl Plugin for LampsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<LampCommand>()
+            .add_systems(Startup, spawn_lamps)
+            .add_systems(FixedUpdate, (handle_lamp_commands, run_lamps).chain())
+            .add_systems(Update, show_lamps);
+    }
+}
+
+const INSERT_RADIUS: f32 = crate::PIXELS_PER_METER * 0.012;
+const GI_RADIUS: f32 = crate::PIXELS_PER_METER * 0.008;
+/// General illumination burns dimmer than the inserts.
+const GI_BRIGHTNESS: f32 = 0.6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LampState {
+    Off,
+    On,
+    /// On and off, `period` seconds for each full cycle.
+    Blink {
+        period: f32,
+    },
+    /// On for `duration` seconds, then off.
+    Flash {
+        duration: f32,
+    },
+}
+
+/// Changes the lamp with this `Name`. Brightness and color stay as they are
+/// unless given.
+#[derive(Message, Clone, Debug)]
+pub struct LampCommand {
+    pub lamp: String,
+    pub state: LampState,
+    pub brightness: Option<f32>,
+    pub color: Option<Color>,
+}
+
+impl LampCommand {
+    pub fn new(lamp: impl Into<String>, state: LampState) -> Self {
+        Self {
+            lamp: lamp.into(),
+            state,
+            brightness: None,
+            color: None,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub(crate) struct Lamp {
+    state: LampState,
+    /// 0 to 1.
+    brightness: f32,
+    color: Color,
+    /// Time since the state was last set.
+    elapsed: f32,
+}
+
+impl Lamp {
+    /// Color the lamp is drawn in while lit; dimmer lamps let more of the
+    /// dark playfield show through.
+    pub fn fill_color(&self) -> Color {
+        self.color.with_alpha(self.brightness)
+    }
+
+    /// Whether the lamp is giving light right now.
+    pub fn is_lit(&self) -> bool {
+        match self.state {
+            LampState::Off => false,
+            LampState::On => true,
+            LampState::Blink { period } => self.elapsed % period < period / 2.0,
+            LampState::Flash { duration } => self.elapsed < duration,
+        }
+    }
+}
+
+/// A round lamp at `position`, relative to its parent if it has one.
+pub(crate) fn lamp(
+    name: &str,
+    position: Vec2,
+    radius: f32,
+    color: Color,
+    brightness: f32,
+    state: LampState,
+) -> impl Bundle + use<> {
+    let shape_lamp = shapes::Circle {
+        radius,
+        center: Vec2::ZERO,
+    };
+    (
+        Name::from(name),
+        ShapeBuilder::with(&shape_lamp).fill(color).build(),
+        // Slightly above whatever it lights up
+        Transform::from_translation(position.extend(0.1)),
+        Visibility::Hidden,
+        Lamp {
+            state,
+            brightness: brightness.clamp(0.0, 1.0),
+            color,
+            elapsed: 0.0,
+        },
+    )
+}
+
+fn spawn_lamps(mut commands: Commands) {
+    // Inserts in front of the lock lane, one per locked ball.
+    for i in 0..crate::ball_lock::LOCK_CAPACITY {
+        commands.spawn(lamp(
+            &format!("Lock {}", i + 1),
+            Vec2::new(0.195, -0.08 - 0.035 * i as f32) * crate::PIXELS_PER_METER,
+            INSERT_RADIUS,
+            bevy::color::palettes::css::RED.into(),
+            1.0,
+            LampState::Off,
+        ));
+    }
+
+    // General illumination along the slingshots.
+    for (name, position) in [
+        ("GI Left Slingshot", Vec2::new(-0.23, -0.2)),
+        ("GI Right Slingshot", Vec2::new(0.13, -0.2)),
+    ] {
+        commands.spawn(lamp(
+            name,
+            position * crate::PIXELS_PER_METER,
+            GI_RADIUS,
+            bevy::color::palettes::css::WHEAT.into(),
+            GI_BRIGHTNESS,
+            LampState::On,
+        ));
+    }
+}
+
+fn handle_lamp_commands(
+    mut lamp_commands: MessageReader<LampCommand>,
+    mut lamps: Query<(&Name, &mut Lamp)>,
+) {
+    for command in lamp_commands.read() {
+        let Some((_, mut lamp)) = lamps
+            .iter_mut()
+            .find(|(name, _)| name.as_str() == command.lamp)
+        else {
+            warn!("No lamp named {}", command.lamp);
+            continue;
+        };
+        lamp.state = command.state;
+        lamp.elapsed = 0.0;
+        if let Some(brightness) = command.brightness {
+            lamp.brightness = brightness.clamp(0.0, 1.0);
+        }
+        if let Some(color) = command.color {
+            lamp.color = color;
+        }
+    }
+}
+
+fn run_lamps(time: Res<Time>, mut lamps: Query<&mut Lamp>) {
+    for mut lamp in &mut lamps {
+        match lamp.state {
+            LampState::Off | LampState::On => {}
+            LampState::Blink { .. } => lamp.elapsed += time.delta_secs(),
+            LampState::Flash { .. } => {
+                lamp.elapsed += time.delta_secs();
+                if !lamp.is_lit() {
+                    lamp.state = LampState::Off;
+                }
+            }
+        }
+    }
+}
+
+fn show_lamps(mut lamps: Query<(&Lamp, &mut Shape, &mut Visibility), Changed<Lamp>>) {
+    for (lamp, mut shape, mut visibility) in &mut lamps {
+        *visibility = if lamp.is_lit() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        shape.fill = Some(Fill::color(lamp.fill_color()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn lamp_lit(sim: &mut Sim, name: &str) -> bool {
+        let world = sim.app.world_mut();
+        let mut lamps = world.query::<(&Name, &Lamp)>();
+        lamps
+            .iter(world)
+            .find(|(lamp_name, _)| lamp_name.as_str() == name)
+            .unwrap()
+            .1
+            .is_lit()
+    }
+
+    #[test]
+    fn lamps_blink_and_flash_on_command() {
+        let mut sim = Sim::new();
+        assert!(!lamp_lit(&mut sim, "Bumper0 Lamp"));
+
+        // Bumper lamps only light on hits, so nothing else commands them. 64 ticks per second.
+        sim.app.world_mut().write_message(LampCommand::new(
+            "Bumper0 Lamp",
+            LampState::Blink { period: 0.5 },
+        ));
+        sim.step(1);
+        assert!(lamp_lit(&mut sim, "Bumper0 Lamp"));
+        sim.step(20);
+        assert!(!lamp_lit(&mut sim, "Bumper0 Lamp"));
+        sim.step(16);
+        assert!(lamp_lit(&mut sim, "Bumper0 Lamp"));
+
+        sim.app.world_mut().write_message(LampCommand::new(
+            "Bumper1 Lamp",
+            LampState::Flash { duration: 0.25 },
+        ));
+        sim.step(1);
+        assert!(lamp_lit(&mut sim, "Bumper1 Lamp"));
+        sim.step(32);
+        assert!(!lamp_lit(&mut sim, "Bumper1 Lamp"));
+    }
+
+    #[test]
+    fn commands_set_brightness_and_color() {
+        let mut sim = Sim::new();
+        let blue = Color::from(bevy::color::palettes::css::BLUE);
+        sim.app.world_mut().write_message(LampCommand {
+            lamp: "Bumper0 Lamp".to_string(),
+            state: LampState::On,
+            brightness: Some(0.25),
+            color: Some(blue),
+        });
+        sim.step(2);
+
+        let world = sim.app.world_mut();
+        let mut lamps = world.query::<(&Name, &Lamp, &Shape)>();
+        let (_, lamp, shape) = lamps
+            .iter(world)
+            .find(|(name, ..)| name.as_str() == "Bumper0 Lamp")
+            .unwrap();
+        assert_eq!(lamp.fill_color(), blue.with_alpha(0.25));
+        assert_eq!(shape.fill.as_ref().unwrap().color, blue.with_alpha(0.25));
+
+        // GI starts out dimmer than the inserts
+        let (_, gi, _) = lamps
+            .iter(world)
+            .find(|(name, ..)| name.as_str() == "GI Left Slingshot")
+            .unwrap();
+        assert_eq!(gi.brightness, GI_BRIGHTNESS);
+    }
+}
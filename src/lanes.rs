@@ -0,0 +1,141 @@
+//! Inlanes, outlanes and slingshots.
+//!
+//! [`spawn_flipper_lanes`] lays out the guides above a flipper from its pivot:
+//! an inlane guide splitting an outlane off on the outside, or the wall where
+//! there is no room for one, and the slingshot triangle whose bottom post sits
+//! just above the flipper. The inlane between them feeds balls down onto the
+//! flipper near its pivot.
+
+use crate::materials::TableMaterial;
+use crate::walls::{GUIDE_THICKNESS, LAUNCHER_WALL_INNER_X, curved_wall, straight};
+use bevy::prelude::*;
+
+pub struct LanesPlugin;
+
+impl Plugin for LanesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_lanes);
+    }
+}
+
+/// How far outside the pivot the inlane guide runs.
+const GUIDE_OUTSET: f32 = crate::PIXELS_PER_METER * 0.05;
+/// Heights of the top and bottom of the inlane guide above the pivot. The top
+/// stays under where the outlane wall curves in, leaving a kicked back ball
+/// room to get out over it.
+const GUIDE_TOP: f32 = crate::PIXELS_PER_METER * 0.2;
+const GUIDE_BOTTOM: f32 = crate::PIXELS_PER_METER * 0.03;
+/// Heights of the top and bottom of the slingshot's back edge above the pivot.
+const SLING_TOP: f32 = crate::PIXELS_PER_METER * 0.24;
+const SLING_BOTTOM: f32 = crate::PIXELS_PER_METER * 0.12;
+/// The slingshot's bottom post, from the pivot. High enough for a ball to roll
+/// under it down the raised flipper into the cradle.
+const SLING_POST: Vec2 = Vec2::new(
+    crate::PIXELS_PER_METER * 0.06,
+    crate::PIXELS_PER_METER * 0.13,
+);
+
+/// Where the guides go around one flipper.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FlipperLanes {
+    pub pivot: Vec2,
+    /// 1 for a left flipper and -1 for a right one, i.e. towards the middle of the table.
+    pub inward: f32,
+    /// Between the inlane guide and the slingshot; zero puts the slingshot
+    /// right against the guide, or the wall if there is no outlane.
+    pub inlane_width: f32,
+    /// Face of the wall the lanes back onto instead of an outlane, or `None`
+    /// for an inlane guide with an outlane outside it.
+    pub wall: Option<f32>,
+}
+
+fn spawn_lanes(mut commands: Commands) {
+    spawn_flipper_lanes(
+        &mut commands,
+        "Left",
+        FlipperLanes {
+            pivot: Vec2::new(-0.3, -0.4) * crate::PIXELS_PER_METER,
+            inward: 1.0,
+            inlane_width: crate::PIXELS_PER_METER * 0.08,
+            wall: None,
+        },
+    );
+    // The shooter lane leaves no room for an outlane on the right.
+    spawn_flipper_lanes(
+        &mut commands,
+        "Right",
+        FlipperLanes {
+            pivot: Vec2::new(0.2, -0.4) * crate::PIXELS_PER_METER,
+            inward: -1.0,
+            inlane_width: 0.0,
+            wall: Some(LAUNCHER_WALL_INNER_X),
+        },
+    );
+}
+
+pub(crate) fn spawn_flipper_lanes(commands: &mut Commands, name: &str, lanes: FlipperLanes) {
+    let FlipperLanes {
+        pivot,
+        inward,
+        inlane_width,
+        wall,
+    } = lanes;
+
+    let back_x = if let Some(wall_x) = wall {
+        wall_x + inward * (GUIDE_THICKNESS / 2.0 + inlane_width)
+    } else {
+        let guide_x = pivot.x - inward * GUIDE_OUTSET;
+        // Leans in at the bottom to end just above the back of the flipper.
+        commands.spawn(curved_wall(
+            &format!("{name} Inlane Guide"),
+            &[straight(
+                Vec2::new(guide_x, pivot.y + GUIDE_TOP),
+                Vec2::new(
+                    guide_x + inward * GUIDE_OUTSET * 0.3,
+                    pivot.y + GUIDE_BOTTOM,
+                ),
+            )],
+            GUIDE_THICKNESS,
+            TableMaterial::Metal,
+        ));
+        guide_x + inward * (GUIDE_THICKNESS / 2.0 + inlane_width)
+    };
+    let top = Vec2::new(back_x, pivot.y + SLING_TOP);
+    let bottom = Vec2::new(back_x, pivot.y + SLING_BOTTOM);
+    let post = pivot + Vec2::new(inward * SLING_POST.x, SLING_POST.y);
+    commands.spawn(curved_wall(
+        &format!("{name} Slingshot"),
+        &[
+            straight(top, post),
+            straight(post, bottom),
+            straight(bottom, top),
+        ],
+        GUIDE_THICKNESS,
+        TableMaterial::Rubber,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::harness::Sim;
+    use bevy::prelude::*;
+
+    #[test]
+    fn inlane_feeds_the_ball_onto_the_raised_flipper() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        sim.press(KeyCode::ArrowLeft);
+        sim.step(10);
+        let inlane = Vec2::new(-0.31, -0.2) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, inlane, Vec2::ZERO);
+        sim.step(192);
+
+        let position = sim
+            .rest_position(ball)
+            .expect("ball drained or still moving");
+        assert!(
+            position.y > crate::PIXELS_PER_METER * -0.42,
+            "ball at {position} not resting on the flipper"
+        );
+    }
+}
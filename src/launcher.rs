@@ -1,3 +1,5 @@
+use crate::input::TableInput;
+use crate::materials::TableMaterial;
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
@@ -7,7 +9,7 @@ pub struct LauncherPlugin;
 impl Plugin for LauncherPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_launcher)
-            .add_systems(Update, launcher_movement);
+            .add_systems(FixedUpdate, launcher_movement);
     }
 }
 
@@ -58,6 +60,8 @@ fn spawn_launcher(mut commands: Commands) {
                 .build(),
             RigidBody::Dynamic,
             Collider::rectangle(shape_launcher.extents.x, shape_launcher.extents.y),
+            // rubber plunger tip
+            TableMaterial::Rubber,
             Transform::from_xyz(launcher_pos.x, launcher_pos.y, 0.0),
             ConstantForce::new(0.0, 0.0),
             // Lock rotation and sideways motion so the plunger only slides along its
@@ -85,10 +89,10 @@ fn spawn_launcher(mut commands: Commands) {
 }
 
 fn launcher_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input: Res<TableInput>,
     mut launchers: Query<(&Launcher, &Transform, &mut ConstantForce), With<Launcher>>,
 ) {
-    // Per-frame pull force in Avian's own units (mass * px/s^2, not Newtons); accumulates while Enter is held.
+    // Per-tick pull force in Avian's own units (mass * px/s^2, not Newtons); accumulates while Enter is held.
     const PULL_FORCE: f32 = 200.0;
     // Maximum pull distance in meters
     const MAX_PULL_DISTANCE: f32 = 0.08;
@@ -96,7 +100,7 @@ fn launcher_movement(
     for (launcher, transform, mut constant_force) in launchers.iter_mut() {
         let current_offset = transform.translation.y - launcher.start_point.y;
 
-        if keyboard_input.pressed(KeyCode::Enter) {
+        if input.plunger {
             // Apply downward force if not at max stretch
             if current_offset > -crate::PIXELS_PER_METER * MAX_PULL_DISTANCE {
                 constant_force.y -= PULL_FORCE;
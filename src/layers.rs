@@ -0,0 +1,40 @@
+//! Collision layers.
+//!
+//! Everything spawned without `CollisionLayers` is on the playfield layer. A
+//! ball moved to another layer only touches what is on that layer, which is
+//! how it can run along a ramp over the playfield below, or play on the upper
+//! playfield above it.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+#[derive(PhysicsLayer, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layer {
+    #[default]
+    Playfield,
+    Ramp,
+    Upper,
+}
+
+impl Layer {
+    /// On this layer and colliding with nothing else.
+    pub fn only(self) -> CollisionLayers {
+        CollisionLayers::new(self, self)
+    }
+
+    /// Depth balls on this layer are drawn at; whatever makes up the layer sits just below.
+    pub fn z(self) -> f32 {
+        match self {
+            Layer::Playfield => 0.0,
+            Layer::Ramp => 1.0,
+            Layer::Upper => 2.0,
+        }
+    }
+
+    /// Moves a ball onto this layer.
+    pub fn enter(self, ball: &mut EntityCommands, transform: &mut Transform) {
+        // CollisionLayers is immutable, so it has to be replaced rather than edited.
+        ball.insert(self.only());
+        transform.translation.z = self.z();
+    }
+}
@@ -0,0 +1,232 @@
+//! Playfield magnets.
+//!
+//! A magnet sits under the playfield and, while energized, pulls on balls
+//! within its radius. Rules drive it with [`MagnetCommand`]s addressed by
+//! name: grab a ball and hold it over the magnet, pulse briefly to bend the
+//! path of a ball rolling past, or fling a held ball away.
+
+use crate::ball::Ball;
+use crate::layers::Layer;
+use crate::table::TableConfig;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct MagnetsPlugin;
+
+impl Plugin for MagnetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<MagnetCommand>()
+            .add_systems(Startup, spawn_magnets)
+            .add_systems(FixedUpdate, (handle_magnet_commands, apply_magnets).chain());
+    }
+}
+
+/// Spring constant pulling a grabbed ball to the magnet's centre, in 1/s^2.
+const GRAB_STIFFNESS: f32 = 400.0;
+/// Pull at the centre of a pulsing magnet, fading to nothing at its radius.
+const PULSE_ACCELERATION: f32 = crate::PIXELS_PER_METER * 8.0;
+const PULSE_TIME: f32 = 0.15;
+/// Speed a flung ball leaves with.
+const FLING_SPEED: f32 = crate::PIXELS_PER_METER * 2.0;
+
+pub const CENTER_MAGNET: &str = "Center Magnet";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MagnetMode {
+    Off,
+    /// Pull balls in and hold them over the magnet.
+    Grab,
+    /// Attract for a moment, bending the path of a ball rolling past.
+    Pulse,
+    /// Let go of the ball, throwing it towards `direction`.
+    Fling {
+        direction: Vec2,
+    },
+}
+
+/// Switches the magnet with this `Name` to `mode`.
+#[derive(Message, Clone, Debug)]
+pub struct MagnetCommand {
+    pub magnet: String,
+    pub mode: MagnetMode,
+}
+
+#[derive(Component)]
+pub(crate) struct Magnet {
+    radius: f32,
+    pub mode: MagnetMode,
+    /// Time left of a pulse.
+    remaining: f32,
+}
+
+fn spawn_magnets(mut commands: Commands) {
+    spawn_magnet(
+        &mut commands,
+        CENTER_MAGNET,
+        Vec2::new(0.05, -0.05) * crate::PIXELS_PER_METER,
+        crate::PIXELS_PER_METER * 0.06,
+    );
+}
+
+fn spawn_magnet(commands: &mut Commands, name: &str, position: Vec2, radius: f32) {
+    let shape_magnet = shapes::Circle {
+        radius,
+        center: Vec2::ZERO,
+    };
+    commands.spawn((
+        Name::from(name.to_string()),
+        ShapeBuilder::with(&shape_magnet)
+            .stroke((bevy::color::palettes::css::DARK_SLATE_GRAY, 2.0))
+            .build(),
+        // under the playfield
+        Transform::from_xyz(position.x, position.y, -0.5),
+        Magnet {
+            radius,
+            mode: MagnetMode::Off,
+            remaining: 0.0,
+        },
+    ));
+}
+
+fn handle_magnet_commands(
+    mut magnet_commands: MessageReader<MagnetCommand>,
+    mut magnets: Query<(&Name, &mut Magnet)>,
+) {
+    for command in magnet_commands.read() {
+        let Some((_, mut magnet)) = magnets
+            .iter_mut()
+            .find(|(name, _)| name.as_str() == command.magnet)
+        else {
+            warn!("No magnet named {}", command.magnet);
+            continue;
+        };
+        magnet.mode = command.mode;
+        magnet.remaining = PULSE_TIME;
+    }
+}
+
+fn apply_magnets(
+    time: Res<Time>,
+    config: Res<TableConfig>,
+    mut magnets: Query<(&Transform, &mut Magnet), Without<Ball>>,
+    mut balls: Query<(&Transform, &CollisionLayers, Forces), With<Ball>>,
+) {
+    for (magnet_transform, mut magnet) in &mut magnets {
+        if magnet.mode == MagnetMode::Off {
+            continue;
+        }
+        let center = magnet_transform.translation.truncate();
+        for (ball_transform, layers, mut forces) in &mut balls {
+            // Ramps and the upper playfield are out of reach.
+            if *layers != Layer::Playfield.only() {
+                continue;
+            }
+            let offset = center - ball_transform.translation.truncate();
+            let distance = offset.length();
+            if distance > magnet.radius {
+                continue;
+            }
+            match magnet.mode {
+                MagnetMode::Off => {}
+                MagnetMode::Grab => {
+                    // Critically damped, so the ball settles without orbiting.
+                    let damping = 2.0 * GRAB_STIFFNESS.sqrt();
+                    let velocity = forces.linear_velocity();
+                    forces.apply_linear_acceleration(GRAB_STIFFNESS * offset - damping * velocity);
+                }
+                MagnetMode::Pulse => {
+                    let pull = PULSE_ACCELERATION * (1.0 - distance / magnet.radius);
+                    forces.apply_linear_acceleration(offset.normalize_or_zero() * pull);
+                }
+                MagnetMode::Fling { direction } => {
+                    forces.apply_linear_impulse(
+                        direction.normalize_or_zero() * FLING_SPEED * config.ball_mass,
+                    );
+                }
+            }
+        }
+
+        match magnet.mode {
+            MagnetMode::Pulse => {
+                magnet.remaining -= time.delta_secs();
+                if magnet.remaining <= 0.0 {
+                    magnet.mode = MagnetMode::Off;
+                }
+            }
+            MagnetMode::Fling { .. } => magnet.mode = MagnetMode::Off,
+            MagnetMode::Off | MagnetMode::Grab => {}
+        }
+    }
+}
+
+/// What the magnet with this `Name` is doing.
+#[cfg(test)]
+pub(crate) fn magnet_mode(world: &mut World, name: &str) -> MagnetMode {
+    world
+        .query::<(&Name, &Magnet)>()
+        .iter(world)
+        .find(|(magnet, _)| magnet.as_str() == name)
+        .unwrap()
+        .1
+        .mode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    const CENTER: Vec2 = Vec2::new(
+        0.05 * crate::PIXELS_PER_METER,
+        -0.05 * crate::PIXELS_PER_METER,
+    );
+
+    fn command(sim: &mut Sim, mode: MagnetMode) {
+        sim.app.world_mut().write_message(MagnetCommand {
+            magnet: CENTER_MAGNET.to_string(),
+            mode,
+        });
+    }
+
+    #[test]
+    fn grabbed_ball_is_held_then_flung() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        command(&mut sim, MagnetMode::Grab);
+        sim.place_ball(ball, CENTER + Vec2::new(15.0, 10.0), Vec2::new(0.0, -100.0));
+
+        sim.step(128);
+        let (position, velocity) = sim.ball_state(ball).unwrap();
+        assert!(
+            position.distance(CENTER) < 5.0 && velocity.length() < 10.0,
+            "ball at {position} moving {velocity} not held"
+        );
+
+        command(
+            &mut sim,
+            MagnetMode::Fling {
+                direction: Vec2::NEG_X,
+            },
+        );
+        sim.step(1);
+        let (_, velocity) = sim.ball_state(ball).unwrap();
+        assert!(velocity.x < -FLING_SPEED * 0.8, "flung at only {velocity}");
+    }
+
+    #[test]
+    fn pulse_switches_itself_off() {
+        let mut sim = Sim::new();
+        command(&mut sim, MagnetMode::Pulse);
+        sim.step(1);
+        assert_eq!(
+            magnet_mode(sim.app.world_mut(), CENTER_MAGNET),
+            MagnetMode::Pulse
+        );
+        sim.step(16);
+        assert_eq!(
+            magnet_mode(sim.app.world_mut(), CENTER_MAGNET),
+            MagnetMode::Off
+        );
+    }
+}
@@ -1,11 +1,10 @@
 use avian2d::PhysicsPlugins;
-use avian2d::dynamics::solver::SolverConfig;
-use avian2d::math::Vector;
-use avian2d::prelude::*;
-use bevy::{prelude::*, window::PresentMode};
-// use bevy_inspector_egui::bevy_egui::EguiPlugin;
-// use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy::camera::ScalingMode;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::{MonitorSelection, PresentMode, WindowMode};
 use bevy_prototype_lyon::prelude::*;
+use clap::Parser;
 
 mod ball;
 use ball::*;
@@ -25,53 +24,274 @@ use bumpers::*;
 mod nudge;
 use nudge::*;
 
+mod rolling;
+use rolling::*;
+
+mod materials;
+use materials::*;
+
+mod gates;
+use gates::*;
+
+mod shooter_lane;
+use shooter_lane::*;
+
+mod rules;
+use rules::*;
+
+mod layers;
+
+mod ramps;
+use ramps::*;
+
+mod upper_playfield;
+use upper_playfield::*;
+
+mod magnets;
+use magnets::*;
+
+mod kickback;
+use kickback::*;
+
+mod lanes;
+use lanes::*;
+
+mod ball_lock;
+use ball_lock::*;
+
+mod coils;
+use coils::*;
+
+mod subway;
+use subway::*;
+
+mod captive;
+use captive::*;
+
+mod lamps;
+use lamps::*;
+
+mod input;
+use input::*;
+
+mod replay;
+use replay::*;
+
+mod trajectory;
+use trajectory::*;
+
+mod table;
+use table::*;
+
+mod cli;
+use cli::*;
+
+mod debug;
+use debug::*;
+
+mod tuning;
+use tuning::*;
+
+#[cfg(test)]
+mod harness;
+
 pub const PIXELS_PER_METER: f32 = 492.3;
 
+/// Part of the table the camera always shows, centred on the origin: out past
+/// the left outlane wall, and from the drain up over the top arch.
+const VIEW_SIZE: Vec2 = Vec2::new(0.92 * PIXELS_PER_METER, 1.32 * PIXELS_PER_METER);
+
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::srgb(0.0, 0.0, 0.0)))
+    match build_app(&Cli::parse()) {
+        Ok(mut app) => {
+            app.run();
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn build_app(cli: &Cli) -> Result<App, String> {
+    let mut app = if cli.headless {
+        headless_app()
+    } else {
+        windowed_app(cli)
+    };
+
+    if let Some(path) = &cli.table {
+        let config = TableConfig::load(path)
+            .map_err(|err| format!("Failed to load table {}: {err}", path.display()))?;
+        app.insert_resource(config);
+    }
+
+    if let Some(path) = &cli.replay {
+        let replay = Replay::load(path)
+            .map_err(|err| format!("Failed to load replay {}: {err}", path.display()))?;
+        // The recorded run's physics, whatever --table says.
+        if let Some(table) = replay.starting_table() {
+            app.insert_resource(table.clone());
+        }
+        app.insert_resource(InputPlayback::new(replay));
+        if cli.headless && cli.ticks.is_none() {
+            app.add_systems(Last, exit_when_replay_finished.in_set(WriteExit));
+        }
+    }
+
+    if let Some(path) = &cli.record {
+        app.insert_resource(InputRecorder::new(path.clone()));
+    }
+    if let Some(path) = &cli.trajectory {
+        let recorder = TrajectoryRecorder::create(path)
+            .map_err(|err| format!("Failed to create {}: {err}", path.display()))?;
+        app.insert_resource(recorder);
+    }
+    if let Some(ticks) = cli.ticks {
+        app.add_systems(
+            Last,
+            (move |tick: Res<InputTick>, mut exit: MessageWriter<AppExit>| {
+                if tick.0 >= ticks {
+                    exit.write(AppExit::Success);
+                }
+            })
+            .in_set(WriteExit),
+        );
+    }
+    // The runner stops after the update the exit is written in, so whatever
+    // saves on exit has to run after it in that same update.
+    app.configure_sets(
+        Last,
+        WriteExit
+            .before(save_recording_on_exit)
+            .before(flush_trajectory_on_exit),
+    );
+
+    Ok(app)
+}
+
+/// Systems ending a headless run.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+struct WriteExit;
+
+fn windowed_app(cli: &Cli) -> App {
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::srgb(0.0, 0.0, 0.0)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Pinball2d".into(),
-                resolution: (360, 640).into(),
-                present_mode: PresentMode::AutoVsync,
+                resolution: cli.resolution.into(),
+                mode: if cli.fullscreen {
+                    WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+                } else {
+                    WindowMode::Windowed
+                },
+                present_mode: if cli.vsync {
+                    PresentMode::AutoVsync
+                } else {
+                    PresentMode::AutoNoVsync
+                },
                 // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
                 prevent_default_event_handling: false,
                 ..default()
             }),
             ..default()
         }))
-        // .add_plugins(EguiPlugin::default())
-        // .add_plugins(WorldInspectorPlugin::new())
-        // Enables avian debug rendering
-        // .add_plugins(PhysicsDebugPlugin)
-        .add_plugins(WallsPlugin)
-        .add_plugins(LauncherPlugin)
-        .add_plugins(FlippersPlugin)
-        .add_plugins(BallPlugin)
-        .add_plugins(BumpersPlugin)
-        .add_plugins(NudgePlugin)
+        .add_plugins(TablePlugin)
         .add_plugins(ShapePlugin)
         .add_systems(Startup, setup)
-        .add_plugins(PhysicsPlugins::default().with_length_unit(PIXELS_PER_METER))
-        .insert_resource(Gravity(Vector::NEG_Y * 520.0))
-        // without this increased substep count, the ball sinks even deeper though flippers and launcher
-        .insert_resource(SubstepCount(20))
-        // Stiffer contacts so the pulled-down plunger's spring/force load doesn't
-        // leak through Avian's soft contacts and let the ball sink into the head.
-        .insert_resource(SolverConfig {
-            contact_frequency_factor: 10.0,
-            // Default threshold is ~492 px/s at this length unit, so slow hits don't bounce
-            // and the ball feels glued; lower it so it bounces realistically at low speed.
-            restitution_threshold: 0.05,
-            ..default()
+        .add_plugins(DebugPlugin)
+        .insert_resource(DebugOverlays {
+            physics_gizmos: cli.physics_debug,
+            inspector: cli.inspector,
+            diagnostics: false,
+            tuning: false,
         })
-        .add_systems(Update, exit_on_escape)
-        .run();
+        .add_plugins(TuningPlugin)
+        .insert_resource(TableFile(
+            cli.table.clone().unwrap_or_else(|| "table.ron".into()),
+        ))
+        .add_systems(Update, exit_on_escape);
+    app
+}
+
+/// The table without a window or renderer, running one fixed tick per update
+/// so it goes as fast as the CPU allows and steps exactly like the game does.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        bevy::input::InputPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        TablePlugin,
+    ))
+    .init_asset::<Mesh>()
+    .insert_resource(TimeUpdateStrategy::FixedTimesteps(1));
+    app
+}
+
+fn exit_when_replay_finished(
+    playback: Option<Res<InputPlayback>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if playback.is_none() {
+        exit.write(AppExit::Success);
+    }
+}
+
+/// The playfield and its physics, without any window, camera or rendering so it
+/// can also run headless.
+pub struct TablePlugin;
+
+impl Plugin for TablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(TableInputPlugin)
+            .add_plugins(ReplayPlugin)
+            .add_plugins(TrajectoryPlugin)
+            .add_plugins(TableConfigPlugin)
+            .add_plugins(WallsPlugin)
+            .add_plugins(LauncherPlugin)
+            .add_plugins(FlippersPlugin)
+            .add_plugins(BallPlugin)
+            .add_plugins(BumpersPlugin)
+            .add_plugins(NudgePlugin)
+            .add_plugins(RollingPlugin)
+            .add_plugins(MaterialsPlugin)
+            .add_plugins(GatesPlugin)
+            .add_plugins(ShooterLanePlugin)
+            .add_plugins(RulesPlugin)
+            .add_plugins(RampsPlugin)
+            .add_plugins(UpperPlayfieldPlugin)
+            .add_plugins(MagnetsPlugin)
+            .add_plugins(KickbackPlugin)
+            .add_plugins(LanesPlugin)
+            .add_plugins(BallLockPlugin)
+            .add_plugins(CoilsPlugin)
+            .add_plugins(SubwayPlugin)
+            .add_plugins(CaptivePlugin)
+            .add_plugins(LampsPlugin)
+            .add_plugins(
+                PhysicsPlugins::default()
+                    .with_length_unit(PIXELS_PER_METER)
+                    .with_collision_hooks::<MaterialContacts>(),
+            );
+    }
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn(Camera2d);
+    // Scaled to fit the table into whatever size the window is.
+    commands.spawn((
+        Camera2d,
+        Projection::from(OrthographicProjection {
+            scaling_mode: ScalingMode::AutoMin {
+                min_width: VIEW_SIZE.x,
+                min_height: VIEW_SIZE.y,
+            },
+            ..OrthographicProjection::default_2d()
+        }),
+    ));
 }
 
 fn exit_on_escape(keyboard: Res<ButtonInput<KeyCode>>, mut exit: MessageWriter<AppExit>) {
@@ -79,3 +299,80 @@ fn exit_on_escape(keyboard: Res<ButtonInput<KeyCode>>, mut exit: MessageWriter<A
         exit.write(AppExit::Success);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{ScriptedInput, Sim};
+    use std::fs;
+
+    #[test]
+    fn headless_run_saves_recording_and_trajectory_on_exit() {
+        let dir = std::env::temp_dir();
+        let record = dir.join("pinball2d_headless_test.ron");
+        let trajectory = dir.join("pinball2d_headless_test.jsonl");
+        let cli = Cli::parse_from([
+            "pinball2d",
+            "--headless",
+            "--ticks",
+            "10",
+            "--record",
+            record.to_str().unwrap(),
+            "--trajectory",
+            trajectory.to_str().unwrap(),
+        ]);
+        build_app(&cli).unwrap().run();
+
+        let replay = Replay::load(&record);
+        let exported = fs::read_to_string(&trajectory).unwrap();
+        fs::remove_file(&record).ok();
+        fs::remove_file(&trajectory).unwrap();
+        assert_eq!(replay.unwrap().end_tick, 10);
+        assert!(!exported.is_empty(), "trajectory never flushed");
+    }
+
+    #[test]
+    fn headless_replay_runs_to_the_recorded_end() {
+        let path = std::env::temp_dir().join("pinball2d_replay_end_test.ron");
+        let mut recorded = Sim::configured(|app| {
+            app.insert_resource(InputRecorder::new(path.clone()));
+        });
+        recorded.play(
+            &[
+                ScriptedInput::press(10, KeyCode::Enter),
+                ScriptedInput::release(70, KeyCode::Enter),
+            ],
+            400,
+        );
+        recorded.app.world_mut().write_message(AppExit::Success);
+        recorded.step(1);
+        let replay = Replay::load(&path).unwrap();
+        assert!(replay.inputs.last().unwrap().tick + 200 < replay.end_tick);
+
+        let cli = Cli::parse_from([
+            "pinball2d",
+            "--headless",
+            "--replay",
+            path.to_str().unwrap(),
+        ]);
+        let mut app = build_app(&cli).unwrap();
+        app.finish();
+        app.cleanup();
+        while app.should_exit().is_none() && app.world().resource::<InputTick>().0 < 1000 {
+            app.update();
+        }
+        fs::remove_file(&path).ok();
+        assert_eq!(app.world().resource::<InputTick>().0, replay.end_tick);
+    }
+
+    #[test]
+    fn bad_paths_are_reported_not_panicked_on() {
+        let missing = std::env::temp_dir().join("pinball2d_no_such_dir/file.ron");
+        let missing = missing.to_str().unwrap();
+        for flag in ["--table", "--replay", "--trajectory"] {
+            let cli = Cli::parse_from(["pinball2d", "--headless", "--ticks", "1", flag, missing]);
+            let err = build_app(&cli).err().unwrap();
+            assert!(err.contains(missing), "{err}");
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Named surface materials.
+//!
+//! Walls, posts, flipper rubbers and the plunger tip are tagged with a
+//! [`TableMaterial`] instead of setting their own `Restitution` and
+//! `Friction`. The values for each material live in the [`TableConfig`], so
+//! retuning "rubber" retunes every rubber on the table at once.
+//!
+//! Restitution also drops with impact speed, like real rubber does:
+//! [`MaterialContacts`] recomputes it for every ball contact from the speed
+//! the ball hits the surface with, so hard flipper shots and bumper hits don't
+//! come back unrealistically lively.
+//!
+//! A part that should be livelier or deader than the rest of its material,
+//! like the right flippers, carries a [`RestitutionScale`] instead of a
+//! material of its own.
+//!
+//! Two balls meeting have no `TableMaterial` between them; their contact uses
+//! the `ball` entry instead, for the steel-on-steel hit and its low friction.
+
+use crate::rolling::Roll;
+use crate::table::TableConfig;
+use avian2d::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct MaterialsPlugin;
+
+impl Plugin for MaterialsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, apply_materials);
+    }
+}
+
+/// What a collider's surface is made of.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableMaterial {
+    Rubber,
+    Metal,
+    Wood,
+    Plastic,
+}
+
+/// Scales the restitution of this collider's [`TableMaterial`].
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct RestitutionScale(pub f32);
+
+/// How a material behaves against the ball.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MaterialProperties {
+    pub restitution: f32,
+    pub friction: f32,
+    /// How fast restitution falls off with impact speed, per m/s.
+    pub falloff: f32,
+}
+
+impl MaterialProperties {
+    /// Restitution for an impact at `speed` m/s along the contact normal.
+    pub fn restitution_at(&self, speed: f32) -> f32 {
+        self.restitution / (1.0 + self.falloff * speed)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Materials {
+    pub rubber: MaterialProperties,
+    pub metal: MaterialProperties,
+    pub wood: MaterialProperties,
+    pub plastic: MaterialProperties,
+    /// One ball against another.
+    pub ball: MaterialProperties,
+}
+
+// Rubber keeps the 0.4 the flippers were first tuned with and the bumpers
+// their 0.7; steel guides and wooden walls soak up more of a hit.
+impl Default for Materials {
+    fn default() -> Self {
+        Self {
+            rubber: MaterialProperties {
+                restitution: 0.4,
+                friction: 0.4,
+                falloff: 0.1,
+            },
+            metal: MaterialProperties {
+                restitution: 0.35,
+                friction: 0.15,
+                falloff: 0.02,
+            },
+            wood: MaterialProperties {
+                restitution: 0.3,
+                friction: 0.3,
+                falloff: 0.02,
+            },
+            plastic: MaterialProperties {
+                restitution: 0.7,
+                friction: 0.2,
+                falloff: 0.05,
+            },
+            ball: MaterialProperties {
+                restitution: 0.9,
+                friction: 0.05,
+                falloff: 0.01,
+            },
+        }
+    }
+}
+
+impl Materials {
+    pub fn get(&self, material: TableMaterial) -> &MaterialProperties {
+        match material {
+            TableMaterial::Rubber => &self.rubber,
+            TableMaterial::Metal => &self.metal,
+            TableMaterial::Wood => &self.wood,
+            TableMaterial::Plastic => &self.plastic,
+        }
+    }
+}
+
+fn apply_materials(
+    config: Res<TableConfig>,
+    colliders: Query<(Entity, Ref<TableMaterial>, Option<&RestitutionScale>)>,
+    mut commands: Commands,
+) {
+    for (entity, material, scale) in &colliders {
+        if !config.is_changed() && !material.is_changed() {
+            continue;
+        }
+        let properties = config.materials.get(*material);
+        let scale = scale.map_or(1.0, |scale| scale.0);
+        commands.entity(entity).insert((
+            Restitution::new(properties.restitution * scale),
+            Friction::new(properties.friction),
+        ));
+    }
+}
+
+/// Collision hooks giving ball contacts their speed-dependent restitution.
+/// Only runs for contacts with a collider that has
+/// `ActiveCollisionHooks::MODIFY_CONTACTS`, which every ball does.
+#[derive(SystemParam)]
+pub struct MaterialContacts<'w, 's> {
+    config: Res<'w, TableConfig>,
+    materials: Query<'w, 's, (&'static TableMaterial, Option<&'static RestitutionScale>)>,
+    bodies: Query<'w, 's, (&'static LinearVelocity, &'static AngularVelocity)>,
+    /// Every ball rolls, captive or in play.
+    balls: Query<'w, 's, (), With<Roll>>,
+}
+
+impl CollisionHooks for MaterialContacts<'_, '_> {
+    fn modify_contacts(&self, contacts: &mut ContactPair, _commands: &mut Commands) -> bool {
+        let colliders = [contacts.collider1, contacts.collider2];
+        let ball_on_ball = colliders
+            .iter()
+            .all(|collider| self.balls.contains(*collider));
+        let (properties, scale) = if let Some((material, scale)) = colliders
+            .iter()
+            .find_map(|collider| self.materials.get(*collider).ok())
+        {
+            (
+                self.config.materials.get(*material),
+                scale.map_or(1.0, |scale| scale.0),
+            )
+        } else if ball_on_ball {
+            (&self.config.materials.ball, 1.0)
+        } else {
+            return true;
+        };
+
+        let body_velocity = |body: Option<Entity>, anchor: Vec2| {
+            body.and_then(|body| self.bodies.get(body).ok())
+                .map_or(Vec2::ZERO, |(linear, angular)| {
+                    linear.0 + angular.0 * anchor.perp()
+                })
+        };
+        for manifold in &mut contacts.manifolds {
+            let impact_speed = manifold
+                .points
+                .iter()
+                .map(|point| {
+                    let relative = body_velocity(contacts.body2, point.anchor2)
+                        - body_velocity(contacts.body1, point.anchor1);
+                    -relative.dot(manifold.normal)
+                })
+                .fold(0.0, f32::max);
+            manifold.restitution = self.config.ball_restitution
+                * scale
+                * properties.restitution_at(impact_speed / crate::PIXELS_PER_METER);
+            if ball_on_ball {
+                // Otherwise the two balls' scale factors would make up the whole coefficient.
+                manifold.friction = self.config.ball_friction * properties.friction;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn wall_restitution(sim: &mut Sim) -> f32 {
+        let world = sim.app.world_mut();
+        let mut walls = world.query::<(&Name, &Restitution)>();
+        walls
+            .iter(world)
+            .find(|(name, _)| name.as_str() == "Wall Left")
+            .map(|(_, restitution)| restitution.coefficient)
+            .unwrap()
+    }
+
+    #[test]
+    fn retuning_a_material_updates_its_colliders() {
+        let mut sim = Sim::new();
+        sim.step(1);
+        assert_eq!(
+            wall_restitution(&mut sim),
+            Materials::default().wood.restitution
+        );
+
+        sim.app
+            .world_mut()
+            .resource_mut::<TableConfig>()
+            .materials
+            .wood
+            .restitution = 0.9;
+        sim.step(1);
+        assert_eq!(wall_restitution(&mut sim), 0.9);
+    }
+
+    /// Fraction of its speed a ball keeps bouncing straight off the left wall.
+    fn rebound(sim: &mut Sim, speed: f32) -> f32 {
+        let ball = sim.ball();
+        let start = Vec2::new(crate::PIXELS_PER_METER * -0.25, 0.0);
+        sim.place_ball(ball, start, Vec2::new(-speed, 0.0));
+        for _ in 0..120 {
+            sim.step(1);
+            let (_, velocity) = sim.ball_state(ball).unwrap();
+            if velocity.x > 0.0 {
+                return velocity.x / speed;
+            }
+        }
+        panic!("ball never came back off the wall");
+    }
+
+    #[test]
+    fn hard_hits_bounce_back_less() {
+        // A level table with lively, strongly falling off wood walls.
+        let mut sim = Sim::configured(|app| {
+            let mut config = TableConfig {
+                slope_degrees: 0.0,
+                ..default()
+            };
+            config.materials.wood = MaterialProperties {
+                restitution: 0.8,
+                friction: 0.0,
+                falloff: 0.5,
+            };
+            app.insert_resource(config);
+        });
+        let soft = rebound(&mut sim, 300.0);
+        let hard = rebound(&mut sim, 1500.0);
+        assert!(
+            hard < soft * 0.8,
+            "soft hit kept {soft:.2} of its speed, hard hit {hard:.2}"
+        );
+    }
+}
@@ -14,6 +14,8 @@
 //!   - visual: the camera is offset by the table's displacement so the playfield
 //!     appears to jolt (purely cosmetic).
 
+use crate::input::TableInput;
+use crate::table::TableConfig;
 use avian2d::math::Vector;
 use avian2d::prelude::*;
 use bevy::prelude::*;
@@ -33,13 +35,19 @@ pub struct NudgePlugin;
 impl Plugin for NudgePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Nudge>()
-            .add_systems(Startup, capture_base_gravity)
-            // Read input every render frame, but step the oscillator and set gravity in
-            // FixedUpdate so it runs in lockstep with Avian's fixed-timestep physics
-            // (FixedPostUpdate); otherwise the ringing gravity gets aliased by the
-            // physics step and the nudge doesn't cancel on a free ball.
-            .add_systems(Update, nudge_input)
-            .add_systems(FixedUpdate, apply_nudge);
+            // Step the oscillator and set gravity in FixedUpdate so it runs in lockstep
+            // with Avian's fixed-timestep physics (FixedPostUpdate); otherwise the ringing
+            // gravity gets aliased by the physics step and the nudge doesn't cancel on a
+            // free ball.
+            .add_systems(
+                FixedUpdate,
+                (
+                    capture_base_gravity.run_if(resource_changed::<TableConfig>),
+                    nudge_input,
+                    apply_nudge,
+                )
+                    .chain(),
+            );
     }
 }
 
@@ -53,28 +61,16 @@ struct Nudge {
     force: Vec2,
 }
 
-fn capture_base_gravity(mut nudge: ResMut<Nudge>, gravity: Res<Gravity>) {
-    nudge.base_gravity = gravity.0;
+fn capture_base_gravity(mut nudge: ResMut<Nudge>, config: Res<TableConfig>) {
+    nudge.base_gravity = Vector::NEG_Y * config.gravity();
 }
 
-fn nudge_input(keyboard: Res<ButtonInput<KeyCode>>, mut nudge: ResMut<Nudge>) {
-    // Keys mirror Visual Pinball defaults: Z = left, / = right, Space = center.
-    // The value is the direction we want the BALL to lurch; the table is shoved the
-    // opposite way. Flip a sign here if a direction feels backwards.
-    let mut ball_dir = Vec2::ZERO;
-    if keyboard.just_pressed(KeyCode::KeyZ) {
-        ball_dir.x -= 1.0; // left nudge
-    }
-    if keyboard.just_pressed(KeyCode::Slash) {
-        ball_dir.x += 1.0; // right nudge
-    }
-    if keyboard.just_pressed(KeyCode::Space) {
-        ball_dir.y -= 1.0; // center nudge: jolt the table up (ball lurches down)
-    }
-
-    if ball_dir != Vec2::ZERO {
+fn nudge_input(input: Res<TableInput>, mut nudge: ResMut<Nudge>) {
+    // The input is the direction we want the BALL to lurch; the table is shoved the
+    // opposite way.
+    if input.nudge != Vec2::ZERO {
         // Accumulate so overlapping nudges add instead of overwriting.
-        nudge.force += -ball_dir.normalize() * NUDGE_PUSH_ACCEL;
+        nudge.force += -input.nudge.normalize() * NUDGE_PUSH_ACCEL;
     }
 }
 
@@ -0,0 +1,242 @@
+//! Wire ramps.
+//!
+//! A ramp is a pair of rails on [`Layer::Ramp`]. A ball going up through the
+//! entrance sensor is moved onto the ramp layer as it leaves it, and then only
+//! touches the rails, so it passes over everything on the layer below. The
+//! exit sensor drops it onto the layer the ramp leads to; the entrance drops
+//! it back where it came from if the ball leaves it going down instead.
+
+use crate::ball::Ball;
+use crate::layers::Layer;
+use crate::materials::TableMaterial;
+use crate::walls::{Curve, capsule_chain, offset_points};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct RampsPlugin;
+
+impl Plugin for RampsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<RampMade>()
+            .add_systems(Startup, spawn_ramps)
+            .add_systems(FixedUpdate, move_balls_between_layers);
+    }
+}
+
+const RAMP_WIDTH: f32 = crate::PIXELS_PER_METER * 0.08;
+const RAIL_THICKNESS: f32 = crate::PIXELS_PER_METER * 0.006;
+
+/// Made with the lock full, it starts multiball.
+pub const LEFT_RAMP: &str = "Left Ramp";
+
+/// Sensor across the mouth of a ramp; `direction` points up the ramp.
+#[derive(Component)]
+struct RampEntrance {
+    direction: Vec2,
+    from: Layer,
+}
+
+#[derive(Component)]
+struct RampExit {
+    ramp: String,
+    to: Layer,
+}
+
+/// A ball made it all the way along the named ramp.
+#[derive(Message, Clone, Debug)]
+pub struct RampMade {
+    pub ramp: String,
+}
+
+fn spawn_ramps(mut commands: Commands) {
+    // From the middle of the left side up over the left and centre bumpers, then
+    // round and down to drop onto the right flipper.
+    spawn_ramp(
+        &mut commands,
+        LEFT_RAMP,
+        &[
+            Curve::Bezier {
+                from: Vec2::new(-0.12, 0.0) * crate::PIXELS_PER_METER,
+                ctrl1: Vec2::new(-0.12, 0.3) * crate::PIXELS_PER_METER,
+                ctrl2: Vec2::new(-0.05, 0.48) * crate::PIXELS_PER_METER,
+                to: Vec2::new(0.05, 0.48) * crate::PIXELS_PER_METER,
+            },
+            Curve::Bezier {
+                from: Vec2::new(0.05, 0.48) * crate::PIXELS_PER_METER,
+                ctrl1: Vec2::new(0.15, 0.48) * crate::PIXELS_PER_METER,
+                ctrl2: Vec2::new(0.15, 0.2) * crate::PIXELS_PER_METER,
+                to: Vec2::new(0.15, -0.12) * crate::PIXELS_PER_METER,
+            },
+        ],
+        Layer::Playfield,
+        Layer::Playfield,
+    );
+}
+
+/// Spawns rails either side of `curves`, with the entrance at their start and
+/// the exit at their end, leading balls from layer `from` to layer `to`.
+pub(crate) fn spawn_ramp(
+    commands: &mut Commands,
+    name: &str,
+    curves: &[Curve],
+    from: Layer,
+    to: Layer,
+) {
+    for (side, offset) in [("Left", RAMP_WIDTH / 2.0), ("Right", -RAMP_WIDTH / 2.0)] {
+        let points = offset_points(curves, offset);
+        let mut path = ShapePath::new().move_to(points[0]);
+        for point in &points[1..] {
+            path = path.line_to(*point);
+        }
+        commands.spawn((
+            Name::from(format!("{name} {side} Rail")),
+            ShapeBuilder::with(&path)
+                .stroke((bevy::color::palettes::css::SILVER, RAIL_THICKNESS))
+                .build(),
+            RigidBody::Static,
+            capsule_chain(&points, RAIL_THICKNESS),
+            Layer::Ramp.only(),
+            TableMaterial::Metal,
+            Transform::from_xyz(0.0, 0.0, Layer::Ramp.z() - 0.1),
+        ));
+    }
+
+    let first = curves[0];
+    let last = curves[curves.len() - 1];
+    let mouth = |position: Vec2, direction: Vec2| {
+        (
+            RigidBody::Static,
+            Collider::rectangle(RAMP_WIDTH, RAIL_THICKNESS),
+            Sensor,
+            Transform::from_translation(position.extend(0.0))
+                .with_rotation(Quat::from_rotation_z(direction.to_angle())),
+        )
+    };
+    // The entrance is on both layers, to catch balls rolling back out as well as in.
+    commands.spawn((
+        Name::from(format!("{name} Entrance")),
+        mouth(first.point(0.0), first.tangent(0.0).perp()),
+        CollisionLayers::new([from, Layer::Ramp], [from, Layer::Ramp]),
+        RampEntrance {
+            direction: first.tangent(0.0),
+            from,
+        },
+    ));
+    commands.spawn((
+        Name::from(format!("{name} Exit")),
+        mouth(last.point(1.0), last.tangent(1.0).perp()),
+        Layer::Ramp.only(),
+        RampExit {
+            ramp: name.to_string(),
+            to,
+        },
+    ));
+}
+
+fn move_balls_between_layers(
+    mut started: MessageReader<CollisionStart>,
+    mut ended: MessageReader<CollisionEnd>,
+    entrances: Query<&RampEntrance>,
+    exits: Query<&RampExit>,
+    mut balls: Query<(&LinearVelocity, &mut Transform), With<Ball>>,
+    mut ramps_made: MessageWriter<RampMade>,
+    mut commands: Commands,
+) {
+    // Splits a contact into the ball and the other body.
+    let ball_and_sensor = |body1: Option<Entity>, body2: Option<Entity>| {
+        let (body1, body2) = (body1?, body2?);
+        if balls.contains(body1) {
+            Some((body1, body2))
+        } else if balls.contains(body2) {
+            Some((body2, body1))
+        } else {
+            None
+        }
+    };
+
+    let mut moves = Vec::new();
+    // A ball can turn back while still on the entrance, so which side it ends up
+    // on is only known once it leaves.
+    for event in ended.read() {
+        let Some((ball, sensor)) = ball_and_sensor(event.body1, event.body2) else {
+            continue;
+        };
+        let (Ok(entrance), Ok((velocity, _))) = (entrances.get(sensor), balls.get(ball)) else {
+            continue;
+        };
+        let layer = if velocity.dot(entrance.direction) > 0.0 {
+            Layer::Ramp
+        } else {
+            entrance.from
+        };
+        moves.push((ball, layer));
+    }
+    for event in started.read() {
+        let Some((ball, sensor)) = ball_and_sensor(event.body1, event.body2) else {
+            continue;
+        };
+        let Ok(exit) = exits.get(sensor) else {
+            continue;
+        };
+        ramps_made.write(RampMade {
+            ramp: exit.ramp.clone(),
+        });
+        moves.push((ball, exit.to));
+    }
+
+    for (ball, layer) in moves {
+        if let Ok((_, mut transform)) = balls.get_mut(ball) {
+            layer.enter(&mut commands.entity(ball), &mut transform);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn on_ramp(sim: &Sim, ball: Entity) -> bool {
+        sim.app.world().get::<CollisionLayers>(ball) == Some(&Layer::Ramp.only())
+    }
+
+    #[test]
+    fn ball_shot_up_the_ramp_comes_down_over_the_right_flipper() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let below_entrance = Vec2::new(-0.12, -0.08) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, below_entrance, Vec2::new(0.0, 1100.0));
+
+        let mut rode_ramp = false;
+        for _ in 0..240 {
+            sim.step(1);
+            rode_ramp |= on_ramp(&sim, ball);
+            let (position, _) = sim.ball_state(ball).unwrap();
+            if rode_ramp && !on_ramp(&sim, ball) {
+                assert!(
+                    position.x > crate::PIXELS_PER_METER * 0.1 && position.y < 0.0,
+                    "ball left the ramp at {position}"
+                );
+                return;
+            }
+        }
+        panic!("ball never made it along the ramp (got on it: {rode_ramp})");
+    }
+
+    #[test]
+    fn ball_rolling_back_down_returns_to_the_playfield() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let below_entrance = Vec2::new(-0.12, -0.08) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, below_entrance, Vec2::new(0.0, 250.0));
+
+        let mut rode_ramp = false;
+        for _ in 0..240 {
+            sim.step(1);
+            rode_ramp |= on_ramp(&sim, ball);
+        }
+        assert!(rode_ramp);
+        assert!(!on_ramp(&sim, ball));
+    }
+}
@@ -0,0 +1,295 @@
+//! Input recording and replay.
+//!
+//! A replay is every change of [`TableInput`] and of the [`TableConfig`],
+//! stamped with the fixed tick it happened on, and the tick the run ended on.
+//! Since the simulation is deterministic for a given input stream, playing a
+//! replay into a fresh game reproduces the original run tick for tick,
+//! including whatever physics glitch a tester hit.
+//!
+//! Recording is enabled by inserting an [`InputRecorder`], playback by
+//! inserting an [`InputPlayback`] (and the [`Replay::starting_table`] from the
+//! replay) before the app starts.
+
+use crate::input::{InputTick, TableInput, latch_input};
+use crate::table::{TableConfig, apply_table_config};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedPreUpdate,
+            (
+                play_back_input.run_if(resource_exists::<InputPlayback>),
+                record_input.run_if(resource_exists::<InputRecorder>),
+                record_table
+                    .run_if(resource_exists::<InputRecorder>.and(resource_changed::<TableConfig>)),
+            )
+                .chain()
+                .after(latch_input)
+                .before(apply_table_config),
+        )
+        .add_systems(
+            Last,
+            save_recording_on_exit.run_if(resource_exists::<InputRecorder>),
+        );
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    /// Input changes in tick order; the input holds until the next entry.
+    pub inputs: Vec<ReplayInput>,
+    /// Table settings in tick order: the ones the run started with, then every
+    /// change made from the tuning panel.
+    #[serde(default)]
+    pub tables: Vec<ReplayTable>,
+    /// Number of ticks the recorded run played, so playback also covers
+    /// whatever happened after the last input change.
+    #[serde(default)]
+    pub end_tick: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub input: TableInput,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTable {
+    pub tick: u64,
+    pub table: TableConfig,
+}
+
+impl Replay {
+    /// The table settings the recorded run started with, if it recorded any.
+    pub fn starting_table(&self) -> Option<&TableConfig> {
+        self.tables.first().map(|entry| &entry.table)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+}
+
+/// Records every input change, written to `path` when the app exits.
+#[derive(Resource)]
+pub struct InputRecorder {
+    pub path: PathBuf,
+    pub replay: Replay,
+    last: TableInput,
+}
+
+impl InputRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            replay: Replay::default(),
+            last: TableInput::default(),
+        }
+    }
+}
+
+/// Feeds a recorded input stream into the game instead of the keyboard, until
+/// the recorded run's last tick, after which the keyboard takes over again.
+#[derive(Resource)]
+pub struct InputPlayback {
+    replay: Replay,
+    next: usize,
+    next_table: usize,
+    current: TableInput,
+}
+
+impl InputPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next: 0,
+            next_table: 0,
+            current: TableInput::default(),
+        }
+    }
+
+    /// True once every recorded input and table change has been applied and
+    /// `tick` is the last one the recorded run played.
+    pub fn finished(&self, tick: u64) -> bool {
+        self.next >= self.replay.inputs.len()
+            && self.next_table >= self.replay.tables.len()
+            && tick + 1 >= self.replay.end_tick
+    }
+}
+
+fn play_back_input(
+    tick: Res<InputTick>,
+    mut playback: ResMut<InputPlayback>,
+    mut input: ResMut<TableInput>,
+    mut config: ResMut<TableConfig>,
+    mut commands: Commands,
+) {
+    // The previous input holds between recorded changes, except nudges, which are one-tick taps.
+    playback.current.nudge = Vec2::ZERO;
+    while let Some(entry) = playback.replay.inputs.get(playback.next).copied() {
+        if entry.tick > tick.0 {
+            break;
+        }
+        playback.current = entry.input;
+        playback.next += 1;
+    }
+    *input = playback.current;
+    while let Some(entry) = playback.replay.tables.get(playback.next_table) {
+        if entry.tick > tick.0 {
+            break;
+        }
+        // The starting table is already in place; only real changes should look like tuning.
+        config.set_if_neq(entry.table.clone());
+        playback.next_table += 1;
+    }
+
+    if playback.finished(tick.0) {
+        info!("Replay finished at tick {}", tick.0);
+        commands.remove_resource::<InputPlayback>();
+    }
+}
+
+fn record_input(tick: Res<InputTick>, input: Res<TableInput>, mut recorder: ResMut<InputRecorder>) {
+    if *input != recorder.last {
+        recorder.replay.inputs.push(ReplayInput {
+            tick: tick.0,
+            input: *input,
+        });
+        recorder.last = *input;
+    }
+}
+
+fn record_table(
+    tick: Res<InputTick>,
+    config: Res<TableConfig>,
+    mut recorder: ResMut<InputRecorder>,
+) {
+    recorder.replay.tables.push(ReplayTable {
+        tick: tick.0,
+        table: config.clone(),
+    });
+}
+
+pub(crate) fn save_recording_on_exit(
+    mut exit: MessageReader<AppExit>,
+    mut recorder: ResMut<InputRecorder>,
+    tick: Res<InputTick>,
+) {
+    if exit.read().next().is_none() {
+        return;
+    }
+    recorder.replay.end_tick = tick.0;
+    match recorder.replay.save(&recorder.path) {
+        Ok(()) => info!("Saved replay to {}", recorder.path.display()),
+        Err(err) => error!(
+            "Failed to save replay to {}: {err}",
+            recorder.path.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{ScriptedInput, Sim};
+
+    fn session() -> Vec<ScriptedInput> {
+        vec![
+            ScriptedInput::press(10, KeyCode::Enter),
+            ScriptedInput::release(70, KeyCode::Enter),
+            ScriptedInput::press(150, KeyCode::Slash),
+            ScriptedInput::release(151, KeyCode::Slash),
+            ScriptedInput::press(220, KeyCode::ShiftLeft),
+            ScriptedInput::release(250, KeyCode::ShiftLeft),
+        ]
+    }
+
+    fn ball_positions(sim: &mut Sim) -> Vec<Vec2> {
+        sim.balls()
+            .into_iter()
+            .filter_map(|ball| sim.ball_state(ball))
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    #[test]
+    fn replay_reproduces_recorded_run() {
+        let mut recorded = Sim::configured(|app| {
+            app.insert_resource(InputRecorder::new(PathBuf::new()));
+        });
+        recorded.play(&session(), 400);
+        let replay = recorded
+            .app
+            .world()
+            .resource::<InputRecorder>()
+            .replay
+            .clone();
+
+        assert!(!replay.inputs.is_empty());
+        let reloaded: Replay = ron::from_str(
+            &ron::ser::to_string_pretty(&replay, ron::ser::PrettyConfig::default()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reloaded, replay);
+
+        let mut replayed = Sim::configured(|app| {
+            app.insert_resource(InputPlayback::new(reloaded));
+        });
+        replayed.step(400);
+
+        assert!(!replayed.app.world().contains_resource::<InputPlayback>());
+        assert_eq!(ball_positions(&mut recorded), ball_positions(&mut replayed));
+    }
+
+    #[test]
+    fn replay_reproduces_run_on_a_tuned_table() {
+        let mut recorded = Sim::configured(|app| {
+            app.insert_resource(TableConfig {
+                slope_degrees: 7.5,
+                ..default()
+            })
+            .insert_resource(InputRecorder::new(PathBuf::new()));
+        });
+        recorded.play(&session(), 300);
+        // Tuned live, with the ball in play
+        recorded
+            .app
+            .world_mut()
+            .resource_mut::<TableConfig>()
+            .ball_restitution = 0.8;
+        recorded.step(100);
+        let replay = recorded
+            .app
+            .world()
+            .resource::<InputRecorder>()
+            .replay
+            .clone();
+        assert_eq!(replay.tables.len(), 2);
+        assert_eq!(replay.starting_table().unwrap().slope_degrees, 7.5);
+
+        let mut replayed = Sim::configured(|app| {
+            app.insert_resource(replay.starting_table().unwrap().clone())
+                .insert_resource(InputPlayback::new(replay));
+        });
+        replayed.step(400);
+
+        assert_eq!(
+            recorded.app.world().resource::<TableConfig>(),
+            replayed.app.world().resource::<TableConfig>()
+        );
+        assert_eq!(ball_positions(&mut recorded), ball_positions(&mut replayed));
+    }
+}
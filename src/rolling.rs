@@ -0,0 +1,197 @@
+//! Rolling ball model.
+//!
+//! The 2D body only knows about spin around the axis pointing out of the
+//! playfield, which Avian's contacts already trade with flippers and walls.
+//! [`Roll`] adds the spin around the two in-plane axes: friction against the
+//! playfield turns a skidding ball into a rolling one, taking 2/7 of its speed
+//! the way a solid sphere does, and rolling resistance slows it from there.
+//! The same spin turns the ball's orientation, shown by markers on its surface.
+//! Only balls free on the main playfield touch it; ramp, upper playfield,
+//! locked and subway balls keep their spin as it is.
+
+use crate::ball::BALL_RADIUS;
+use crate::ball_lock::Locked;
+use crate::layers::Layer;
+use crate::subway::InSubway;
+use crate::table::TableConfig;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct RollingPlugin;
+
+impl Plugin for RollingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, roll_on_playfield)
+            .add_systems(Update, turn_ball_markers);
+    }
+}
+
+const MARKER_RADIUS: f32 = crate::PIXELS_PER_METER * 0.007;
+
+/// Spin and orientation of a ball in three dimensions. The playfield is the
+/// x/y plane with z pointing up out of it, so the ball touches it at `-z`.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub(crate) struct Roll {
+    /// Angular velocity around the playfield's x and y axes, in rad/s.
+    pub spin: Vec2,
+    pub orientation: Quat,
+}
+
+impl Roll {
+    /// A ball rolling without slipping at `velocity`.
+    pub fn rolling(velocity: Vec2) -> Self {
+        Self {
+            spin: Vec2::new(-velocity.y, velocity.x) / BALL_RADIUS,
+            orientation: Quat::IDENTITY,
+        }
+    }
+
+    /// Velocity of the point touching the playfield; zero while rolling.
+    fn slip(&self, velocity: Vec2) -> Vec2 {
+        velocity + BALL_RADIUS * Vec2::new(-self.spin.y, self.spin.x)
+    }
+}
+
+/// A dot painted on the ball, in the given direction in the ball's own frame.
+#[derive(Component)]
+pub(crate) struct BallMarker(Vec3);
+
+pub(crate) fn ball_marker(direction: Vec3) -> impl Bundle {
+    let shape_marker = shapes::Circle {
+        radius: MARKER_RADIUS,
+        center: Vec2::ZERO,
+    };
+    (
+        Name::from("BallMarker"),
+        ShapeBuilder::with(&shape_marker).fill(Color::WHITE).build(),
+        Transform::from_xyz(0.0, 0.0, 0.1),
+        Visibility::Inherited,
+        BallMarker(direction.normalize()),
+    )
+}
+
+/// Balls neither sitting in the lock nor travelling through a subway.
+type Free = (Without<Locked>, Without<InSubway>);
+
+fn roll_on_playfield(
+    time: Res<Time>,
+    config: Res<TableConfig>,
+    mut balls: Query<
+        (
+            &mut LinearVelocity,
+            &AngularVelocity,
+            &mut Roll,
+            &CollisionLayers,
+        ),
+        Free,
+    >,
+) {
+    let dt = time.delta_secs();
+    let normal_gravity = config.normal_gravity();
+    for (mut velocity, angular_velocity, mut roll, layers) in &mut balls {
+        if *layers != Layer::Playfield.only() {
+            continue;
+        }
+        // Friction changes the slip 7/2 times as fast as the velocity for a solid
+        // sphere, so 2/7 of the slip is the change that leaves the ball rolling.
+        let slip = roll.slip(velocity.0);
+        let max_change = config.table_friction * normal_gravity * dt;
+        let delta_v = if slip.length() * 2.0 / 7.0 <= max_change {
+            -slip * 2.0 / 7.0
+        } else {
+            -slip.normalize() * max_change
+        };
+        velocity.0 += delta_v;
+        // The same friction, acting at the contact point, spins the ball (I = 2/5 m r^2).
+        roll.spin += 2.5 / BALL_RADIUS * Vec2::new(delta_v.y, -delta_v.x);
+
+        let speed = velocity.length();
+        if speed > 0.0 {
+            let slowed = (speed - config.rolling_resistance * normal_gravity * dt).max(0.0);
+            velocity.0 *= slowed / speed;
+            roll.spin *= slowed / speed;
+        }
+
+        let turn = Vec3::new(roll.spin.x, roll.spin.y, angular_velocity.0) * dt;
+        roll.orientation = (Quat::from_scaled_axis(turn) * roll.orientation).normalize();
+    }
+}
+
+fn turn_ball_markers(
+    balls: Query<(&Roll, &Transform, &Children)>,
+    mut markers: Query<(&BallMarker, &mut Transform, &mut Visibility), Without<Roll>>,
+) {
+    for (roll, ball_transform, children) in &balls {
+        // Avian already turns the ball's transform with its flat spin, which `orientation`
+        // includes too, so undo it for the children.
+        let unturn = ball_transform.rotation.inverse();
+        for child in children.iter() {
+            if let Ok((marker, mut transform, mut visibility)) = markers.get_mut(child) {
+                let point = roll.orientation * marker.0 * (BALL_RADIUS - MARKER_RADIUS);
+                transform.translation = (unturn * point.with_z(0.0)).with_z(0.1);
+                *visibility = if point.z >= 0.0 {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    #[test]
+    fn skidding_ball_rolls_on_at_five_sevenths_of_its_speed() {
+        // A level table, so only the playfield friction acts on the ball.
+        let mut sim = Sim::configured(|app| {
+            app.insert_resource(TableConfig {
+                slope_degrees: 0.0,
+                rolling_resistance: 0.0,
+                ..default()
+            });
+        });
+        let ball = sim.ball();
+        let start = Vec2::new(crate::PIXELS_PER_METER * -0.1, 0.0);
+        sim.place_ball(ball, start, Vec2::new(200.0, 0.0));
+        sim.app.world_mut().entity_mut(ball).insert(Roll::default());
+
+        sim.step(20);
+
+        let (_, velocity) = sim.ball_state(ball).unwrap();
+        assert!(
+            (velocity.x - 200.0 * 5.0 / 7.0).abs() < 2.0 && velocity.y.abs() < 1.0,
+            "ball ended up at {velocity} px/s"
+        );
+        let roll = sim.app.world().get::<Roll>(ball).unwrap();
+        assert!(roll.slip(velocity).length() < 1.0);
+    }
+
+    #[test]
+    fn balls_off_the_playfield_keep_their_spin() {
+        let mut sim = Sim::new();
+        let locked = sim.ball();
+        let on_ramp = sim.spawn_ball();
+        for (ball, x) in [(locked, -0.1), (on_ramp, 0.0)] {
+            let start = Vec2::new(crate::PIXELS_PER_METER * x, 0.0);
+            sim.place_ball(ball, start, Vec2::new(200.0, 0.0));
+            sim.app.world_mut().entity_mut(ball).insert(Roll::default());
+        }
+        sim.app.world_mut().entity_mut(locked).insert(Locked);
+        sim.app
+            .world_mut()
+            .entity_mut(on_ramp)
+            .insert(Layer::Ramp.only());
+
+        sim.step(4);
+
+        for ball in [locked, on_ramp] {
+            let roll = sim.app.world().get::<Roll>(ball).unwrap();
+            assert_eq!(roll.spin, Vec2::ZERO);
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! Game rules.
+//!
+//! Rule timers run on the fixed tick, like the physics, so they expire at
+//! the same point in a replay as in the original run. Both the ball save and
+//! the skill shot window start when the ball leaves the shooter lane; the last
+//! ball in play draining while the ball save is on is served again. Every
+//! new ball relights the kickback. The lock diverter closes the lock lane
+//! while the lock is full, and making the left ramp then starts multiball.
+//! Knocking the captive ball up its lane scores, raises the centre post for a
+//! while and pulses the centre magnet. Dropping into the top subway scores,
+//! and the ball coming back out turns the centre magnet on to grab the next
+//! ball rolling over it, and fling it at the left ramp when the hold runs out.
+
+use crate::ball::{BallServed, Serve};
+use crate::ball_lock::{BallLock, LOCK_CAPACITY, StartMultiball};
+use crate::captive::CaptiveBallHit;
+use crate::coils::{CENTER_POST, CoilCommand, LOCK_DIVERTER};
+use crate::kickback::LightKickback;
+use crate::lamps::{LampCommand, LampState};
+use crate::magnets::{CENTER_MAGNET, MagnetCommand, MagnetMode};
+use crate::ramps::{LEFT_RAMP, RampMade};
+use crate::shooter_lane::BallLeftShooterLane;
+use crate::subway::{BallEnteredSubway, BallLeftSubway, TOP_SUBWAY};
+use bevy::prelude::*;
+use std::time::Duration;
+
+pub struct RulesPlugin;
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RuleTimers>()
+            .init_resource::<Score>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    tick_rule_timers,
+                    start_timers_on_launch,
+                    relight_kickback_for_new_ball,
+                    start_multiball_from_left_ramp,
+                    score_captive_ball,
+                    score_subway,
+                    raise_center_post_for_captive_ball,
+                    work_center_magnet,
+                    (close_lock_when_full, light_lock_inserts).run_if(resource_changed::<BallLock>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Length of the ball save window after launch.
+const BALL_SAVE_TIME: Duration = Duration::from_secs(8);
+/// Length of the skill shot window after launch.
+const SKILL_SHOT_TIME: Duration = Duration::from_secs(3);
+
+const CAPTIVE_BALL_POINTS: u64 = 5_000;
+const TOP_SUBWAY_POINTS: u64 = 10_000;
+/// How long the centre post stays up after the captive ball is hit.
+const CENTER_POST_TIME: Duration = Duration::from_secs(10);
+/// How long the centre magnet holds on after a ball comes out of the subway.
+const MAGNET_HOLD_TIME: Duration = Duration::from_secs(5);
+/// From the centre magnet towards the left ramp entrance.
+const MAGNET_FLING: Vec2 = Vec2::new(-0.17, 0.05);
+/// Lock inserts blink this fast once the lock is full and multiball is ready.
+const MULTIBALL_READY_BLINK: f32 = 0.4;
+
+#[derive(Resource, Default)]
+pub struct Score(pub u64);
+
+/// Timed rule windows. The skill shot window only shows in the game panel so
+/// far, as there is no skill shot target yet.
+#[derive(Resource)]
+pub struct RuleTimers {
+    pub ball_save: Timer,
+    pub skill_shot: Timer,
+    pub center_post: Timer,
+    pub magnet: Timer,
+}
+
+impl Default for RuleTimers {
+    fn default() -> Self {
+        // All start out finished, i.e. inactive until first started.
+        let finished = |duration| {
+            let mut timer = Timer::new(duration, TimerMode::Once);
+            timer.tick(duration);
+            timer
+        };
+        Self {
+            ball_save: finished(BALL_SAVE_TIME),
+            skill_shot: finished(SKILL_SHOT_TIME),
+            center_post: finished(CENTER_POST_TIME),
+            magnet: finished(MAGNET_HOLD_TIME),
+        }
+    }
+}
+
+impl RuleTimers {
+    pub fn ball_save_active(&self) -> bool {
+        !self.ball_save.is_finished()
+    }
+
+    pub fn skill_shot_active(&self) -> bool {
+        !self.skill_shot.is_finished()
+    }
+}
+
+fn tick_rule_timers(time: Res<Time>, mut timers: ResMut<RuleTimers>) {
+    timers.ball_save.tick(time.delta());
+    timers.skill_shot.tick(time.delta());
+    timers.center_post.tick(time.delta());
+    timers.magnet.tick(time.delta());
+}
+
+fn start_timers_on_launch(
+    mut left_lane: MessageReader<BallLeftShooterLane>,
+    mut timers: ResMut<RuleTimers>,
+) {
+    if left_lane.read().count() > 0 {
+        timers.ball_save.reset();
+        timers.skill_shot.reset();
+    }
+}
+
+fn relight_kickback_for_new_ball(
+    mut served: MessageReader<BallServed>,
+    mut light_kickback: MessageWriter<LightKickback>,
+) {
+    // Not for a ball standing in for a locked one, in the middle of play.
+    let next_balls = served
+        .read()
+        .filter(|served| served.serve == Serve::NextBall)
+        .count();
+    if next_balls > 0 {
+        light_kickback.write(LightKickback);
+    }
+}
+
+fn start_multiball_from_left_ramp(
+    mut ramps_made: MessageReader<RampMade>,
+    lock: Res<BallLock>,
+    mut start_multiball: MessageWriter<StartMultiball>,
+) {
+    let made = ramps_made
+        .read()
+        .filter(|made| made.ramp == LEFT_RAMP)
+        .count();
+    if made > 0 && lock.is_full() {
+        start_multiball.write(StartMultiball);
+    }
+}
+
+fn score_captive_ball(mut hits: MessageReader<CaptiveBallHit>, mut score: ResMut<Score>) {
+    score.0 += hits.read().count() as u64 * CAPTIVE_BALL_POINTS;
+}
+
+fn score_subway(mut entered: MessageReader<BallEnteredSubway>, mut score: ResMut<Score>) {
+    let top_subway = entered
+        .read()
+        .filter(|entered| entered.hole == TOP_SUBWAY)
+        .count();
+    score.0 += top_subway as u64 * TOP_SUBWAY_POINTS;
+}
+
+fn raise_center_post_for_captive_ball(
+    mut hits: MessageReader<CaptiveBallHit>,
+    mut timers: ResMut<RuleTimers>,
+    mut coils: MessageWriter<CoilCommand>,
+) {
+    let energized = if hits.read().count() > 0 {
+        timers.center_post.reset();
+        true
+    } else if timers.center_post.just_finished() {
+        false
+    } else {
+        return;
+    };
+    coils.write(CoilCommand {
+        coil: CENTER_POST.to_string(),
+        energized,
+    });
+}
+
+fn work_center_magnet(
+    mut left_subway: MessageReader<BallLeftSubway>,
+    mut captive_hits: MessageReader<CaptiveBallHit>,
+    mut timers: ResMut<RuleTimers>,
+    mut magnets: MessageWriter<MagnetCommand>,
+) {
+    let from_subway = left_subway
+        .read()
+        .filter(|left| left.hole == TOP_SUBWAY)
+        .count();
+    let captive_hit = captive_hits.read().count() > 0;
+    let mode = if from_subway > 0 {
+        timers.magnet.reset();
+        MagnetMode::Grab
+    } else if timers.magnet.just_finished() {
+        MagnetMode::Fling {
+            direction: MAGNET_FLING,
+        }
+    } else if captive_hit && timers.magnet.is_finished() {
+        // Not while holding a ball, which the pulse would let go of.
+        MagnetMode::Pulse
+    } else {
+        return;
+    };
+    magnets.write(MagnetCommand {
+        magnet: CENTER_MAGNET.to_string(),
+        mode,
+    });
+}
+
+/// Only when the lock changes, so other commands to the diverter stick in
+/// between. The diverter starts at rest, which is right for the empty lock.
+fn close_lock_when_full(lock: Res<BallLock>, mut coils: MessageWriter<CoilCommand>) {
+    if !lock.is_changed() || lock.is_added() {
+        return;
+    }
+    coils.write(CoilCommand {
+        coil: LOCK_DIVERTER.to_string(),
+        energized: lock.is_full(),
+    });
+}
+
+fn light_lock_inserts(lock: Res<BallLock>, mut lamps: MessageWriter<LampCommand>) {
+    for i in 0..LOCK_CAPACITY {
+        let state = if lock.is_full() {
+            LampState::Blink {
+                period: MULTIBALL_READY_BLINK,
+            }
+        } else if i < lock.locked {
+            LampState::On
+        } else {
+            LampState::Off
+        };
+        lamps.write(LampCommand::new(format!("Lock {}", i + 1), state));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+    use crate::kickback::kickback_lit;
+    use crate::magnets::magnet_mode;
+
+    #[test]
+    fn launch_opens_ball_save_and_skill_shot_windows() {
+        let mut sim = Sim::new();
+        let timers = sim.app.world().resource::<RuleTimers>();
+        assert!(!timers.ball_save_active() && !timers.skill_shot_active());
+
+        let ball = sim.ball();
+        sim.app
+            .world_mut()
+            .write_message(BallLeftShooterLane { ball });
+        sim.step(1);
+        let timers = sim.app.world().resource::<RuleTimers>();
+        assert!(timers.ball_save_active() && timers.skill_shot_active());
+
+        // 64 ticks per second
+        sim.step(4 * 64);
+        let timers = sim.app.world().resource::<RuleTimers>();
+        assert!(timers.ball_save_active() && !timers.skill_shot_active());
+
+        sim.step(5 * 64);
+        assert!(!sim.app.world().resource::<RuleTimers>().ball_save_active());
+    }
+
+    #[test]
+    fn captive_ball_raises_the_center_post_for_a_while() {
+        let mut sim = Sim::new();
+        let post_up = |sim: &mut Sim| {
+            let world = sim.app.world_mut();
+            let mut coils = world.query::<(&Name, &crate::coils::Coil)>();
+            coils
+                .iter(world)
+                .find(|(name, _)| name.as_str() == CENTER_POST)
+                .unwrap()
+                .1
+                .energized
+        };
+        assert!(!post_up(&mut sim));
+
+        sim.app.world_mut().write_message(CaptiveBallHit);
+        sim.step(2);
+        assert!(post_up(&mut sim));
+
+        sim.step(10 * 64 + 4);
+        assert!(!post_up(&mut sim));
+    }
+
+    #[test]
+    fn ball_out_of_the_subway_has_the_magnet_grab_and_fling_the_next_ball() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let magnet = Vec2::new(0.05, -0.05) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, magnet, Vec2::ZERO);
+        sim.app.world_mut().write_message(BallLeftSubway {
+            hole: TOP_SUBWAY.to_string(),
+        });
+        sim.step(2);
+        assert_eq!(
+            magnet_mode(sim.app.world_mut(), CENTER_MAGNET),
+            MagnetMode::Grab
+        );
+
+        // A captive ball hit doesn't let go of a held ball
+        sim.app.world_mut().write_message(CaptiveBallHit);
+        sim.step(2);
+        assert_eq!(
+            magnet_mode(sim.app.world_mut(), CENTER_MAGNET),
+            MagnetMode::Grab
+        );
+
+        sim.step(5 * 64 + 4);
+        let (_, velocity) = sim.ball_state(ball).unwrap();
+        assert!(velocity.x < 0.0, "ball not flung, moving {velocity}");
+        assert_eq!(
+            magnet_mode(sim.app.world_mut(), CENTER_MAGNET),
+            MagnetMode::Off
+        );
+    }
+
+    #[test]
+    fn captive_ball_hit_pulses_the_magnet() {
+        let mut sim = Sim::new();
+        sim.app.world_mut().write_message(CaptiveBallHit);
+        sim.step(2);
+        assert_eq!(
+            magnet_mode(sim.app.world_mut(), CENTER_MAGNET),
+            MagnetMode::Pulse
+        );
+    }
+
+    #[test]
+    fn top_subway_scores() {
+        let mut sim = Sim::new();
+        sim.app.world_mut().write_message(BallEnteredSubway {
+            hole: TOP_SUBWAY.to_string(),
+        });
+        sim.step(1);
+        assert_eq!(sim.app.world().resource::<Score>().0, TOP_SUBWAY_POINTS);
+    }
+
+    #[test]
+    fn only_the_next_ball_relights_the_kickback() {
+        let mut sim = Sim::new();
+        let relit_after = |sim: &mut Sim, serve: Serve| {
+            let world = sim.app.world_mut();
+            world
+                .query::<&mut crate::kickback::Kickback>()
+                .single_mut(world)
+                .unwrap()
+                .lit = false;
+            let ball = sim.ball();
+            sim.app
+                .world_mut()
+                .write_message(BallServed { ball, serve });
+            sim.step(2);
+            kickback_lit(sim.app.world_mut())
+        };
+        // let the first ball's relight through before starting
+        sim.step(2);
+        assert!(!relit_after(&mut sim, Serve::Replacement));
+        assert!(relit_after(&mut sim, Serve::NextBall));
+    }
+
+    #[test]
+    fn ball_drained_during_the_ball_save_is_served_again() {
+        let mut sim = Sim::new();
+        let drain = Vec2::new(-0.05, -0.55) * crate::PIXELS_PER_METER;
+        let served_after_drain = |sim: &mut Sim| {
+            let ball = sim.ball();
+            sim.place_ball(ball, drain, Vec2::new(0.0, -300.0));
+            // Messages only last two ticks, so read them as they come.
+            let mut cursor = sim
+                .app
+                .world()
+                .resource::<Messages<BallServed>>()
+                .get_cursor_current();
+            let mut served = Vec::new();
+            for _ in 0..16 {
+                sim.step(1);
+                let messages = sim.app.world().resource::<Messages<BallServed>>();
+                served.extend(cursor.read(messages).map(|served| served.serve));
+            }
+            assert!(sim.ball_state(ball).is_none(), "ball didn't drain");
+            served
+        };
+        let ball = sim.ball();
+        sim.app
+            .world_mut()
+            .write_message(BallLeftShooterLane { ball });
+        sim.step(1);
+
+        assert_eq!(served_after_drain(&mut sim), vec![Serve::Replacement]);
+        // only once
+        assert_eq!(served_after_drain(&mut sim), vec![Serve::NextBall]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Shooter lane exit.
+//!
+//! A sensor just above the shooter lane gate sends [`BallLeftShooterLane`]
+//! when a launched ball passes it on the way up. From then on the gate is
+//! latched shut, so nothing gets back into the lane, until the next ball is
+//! served. Only the served ball counts: another ball flying up past the sensor
+//! mustn't shut the gate on one still waiting in the lane.
+
+use crate::ball::{Ball, BallServed};
+use crate::gates::Gate;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct ShooterLanePlugin;
+
+impl Plugin for ShooterLanePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<BallLeftShooterLane>()
+            .add_systems(Startup, spawn_lane_exit)
+            .add_systems(
+                FixedUpdate,
+                (
+                    detect_ball_leaving_lane,
+                    latch_shooter_lane_gate,
+                    release_shooter_lane_gate,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// A launched ball made it out of the shooter lane onto the playfield.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct BallLeftShooterLane {
+    pub ball: Entity,
+}
+
+/// On the joint of the gate at the top of the shooter lane.
+#[derive(Component)]
+pub(crate) struct ShooterLaneGate;
+
+#[derive(Component)]
+struct ShooterLaneExit;
+
+/// On a served ball until it has left the shooter lane.
+#[derive(Component)]
+struct InShooterLane;
+
+fn spawn_lane_exit(mut commands: Commands) {
+    let shape_exit = shapes::Rectangle {
+        extents: Vec2::new(
+            crate::PIXELS_PER_METER * 0.07,
+            crate::PIXELS_PER_METER * 0.02,
+        ),
+        origin: shapes::RectangleOrigin::Center,
+        radii: None,
+    };
+
+    commands.spawn((
+        Name::from("Shooter Lane Exit"),
+        RigidBody::Static,
+        Collider::rectangle(shape_exit.extents.x, shape_exit.extents.y),
+        Sensor,
+        Transform::from_xyz(
+            crate::PIXELS_PER_METER * 0.3,
+            crate::PIXELS_PER_METER * 0.32,
+            0.0,
+        ),
+        ShooterLaneExit,
+    ));
+}
+
+fn detect_ball_leaving_lane(
+    mut collision_reader: MessageReader<CollisionStart>,
+    exits: Query<(), With<ShooterLaneExit>>,
+    balls: Query<&LinearVelocity, With<Ball>>,
+    mut left_lane: MessageWriter<BallLeftShooterLane>,
+) {
+    for event in collision_reader.read() {
+        let (Some(body1), Some(body2)) = (event.body1, event.body2) else {
+            continue;
+        };
+        let ball = if exits.contains(body1) {
+            body2
+        } else if exits.contains(body2) {
+            body1
+        } else {
+            continue;
+        };
+        // Balls coming down the orbit cross the sensor too.
+        if balls.get(ball).is_ok_and(|velocity| velocity.y > 0.0) {
+            left_lane.write(BallLeftShooterLane { ball });
+        }
+    }
+}
+
+fn latch_shooter_lane_gate(
+    mut left_lane: MessageReader<BallLeftShooterLane>,
+    lane_balls: Query<(), With<InShooterLane>>,
+    mut gates: Query<(&Gate, &mut RevoluteJoint), With<ShooterLaneGate>>,
+    mut commands: Commands,
+) {
+    let mut served_ball_left = false;
+    for left in left_lane.read() {
+        if lane_balls.contains(left.ball) {
+            commands.entity(left.ball).remove::<InShooterLane>();
+            served_ball_left = true;
+        }
+    }
+    if !served_ball_left {
+        return;
+    }
+    for (gate, mut joint) in &mut gates {
+        gate.latch(&mut joint);
+    }
+}
+
+fn release_shooter_lane_gate(
+    mut served: MessageReader<BallServed>,
+    mut gates: Query<(&Gate, &mut RevoluteJoint), With<ShooterLaneGate>>,
+    mut commands: Commands,
+) {
+    // Every served ball, replacements too, needs the way out of the lane open.
+    let mut any_served = false;
+    for served in served.read() {
+        commands.entity(served.ball).insert(InShooterLane);
+        any_served = true;
+    }
+    if !any_served {
+        return;
+    }
+    for (gate, mut joint) in &mut gates {
+        gate.release(&mut joint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn gate_latched(sim: &mut Sim) -> bool {
+        let world = sim.app.world_mut();
+        let mut joints = world.query_filtered::<&RevoluteJoint, With<ShooterLaneGate>>();
+        let joint = joints.single(world).unwrap();
+        joint
+            .angle_limit
+            .is_some_and(|limit| limit.min == 0.0 && limit.max == 0.0)
+    }
+
+    #[test]
+    fn gate_latches_once_the_launched_ball_is_out() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let lane = Vec2::new(crate::PIXELS_PER_METER * 0.3, 0.0);
+        sim.place_ball(ball, lane, Vec2::new(0.0, 1500.0));
+        assert!(!gate_latched(&mut sim));
+
+        sim.step(40);
+        assert!(gate_latched(&mut sim));
+
+        sim.spawn_ball();
+        sim.step(1);
+        assert!(!gate_latched(&mut sim));
+    }
+
+    #[test]
+    fn only_the_served_ball_leaving_latches_the_gate() {
+        let mut sim = Sim::new();
+        let first = sim.ball();
+        // The first tick handles the serve from startup.
+        sim.step(1);
+        sim.app
+            .world_mut()
+            .write_message(BallLeftShooterLane { ball: first });
+        sim.step(1);
+        assert!(gate_latched(&mut sim));
+
+        // Out of the way of the next ball, up on the playfield
+        sim.place_ball(
+            first,
+            Vec2::new(0.0, crate::PIXELS_PER_METER * 0.1),
+            Vec2::ZERO,
+        );
+        let second = sim.spawn_ball();
+        sim.step(1);
+        assert!(!gate_latched(&mut sim));
+
+        sim.app
+            .world_mut()
+            .write_message(BallLeftShooterLane { ball: first });
+        sim.step(1);
+        assert!(!gate_latched(&mut sim));
+
+        sim.app
+            .world_mut()
+            .write_message(BallLeftShooterLane { ball: second });
+        sim.step(1);
+        assert!(gate_latched(&mut sim));
+    }
+
+    #[test]
+    fn ball_coming_down_the_orbit_does_not_count_as_leaving() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let orbit = Vec2::new(crate::PIXELS_PER_METER * 0.3, crate::PIXELS_PER_METER * 0.4);
+        sim.place_ball(ball, orbit, Vec2::new(0.0, -300.0));
+        sim.step(60);
+        assert!(!gate_latched(&mut sim));
+    }
+}
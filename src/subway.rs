@@ -0,0 +1,196 @@
+//! Subways and up-kickers.
+//!
+//! A ball rolling over a subway hole drops out of play: it stops being
+//! simulated and is hidden, but keeps its entity. After the hole's delay it
+//! comes back at the hole's exit with the exit velocity, as if kicked up out
+//! of a VUK somewhere else on the table. Rules hear about both ends through
+//! [`BallEnteredSubway`] and [`BallLeftSubway`].
+
+use crate::ball::{BALL_RADIUS, Ball};
+use crate::layers::Layer;
+use crate::rolling::Roll;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+pub struct SubwayPlugin;
+
+impl Plugin for SubwayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<BallEnteredSubway>()
+            .add_message::<BallLeftSubway>()
+            .add_systems(Startup, spawn_subways)
+            .add_systems(FixedUpdate, (capture_balls, emit_balls).chain());
+    }
+}
+
+/// Size of the sensor in the middle of the hole; a ball touching it drops in.
+const CAPTURE_RADIUS: f32 = crate::PIXELS_PER_METER * 0.01;
+
+pub const TOP_SUBWAY: &str = "Top Subway";
+
+#[derive(Message, Clone, Debug)]
+pub struct BallEnteredSubway {
+    pub hole: String,
+}
+
+#[derive(Message, Clone, Debug)]
+pub struct BallLeftSubway {
+    pub hole: String,
+}
+
+#[derive(Component, Clone)]
+struct SubwayHole {
+    exit: Vec2,
+    exit_velocity: Vec2,
+    delay: f32,
+}
+
+/// On a ball travelling through a subway.
+#[derive(Component)]
+pub(crate) struct InSubway {
+    hole: String,
+    exit: Vec2,
+    exit_velocity: Vec2,
+    remaining: f32,
+}
+
+fn spawn_subways(mut commands: Commands) {
+    // Between the top bumpers, coming back out at the top of the left inlane.
+    spawn_subway_hole(
+        &mut commands,
+        TOP_SUBWAY,
+        Vec2::new(-0.05, 0.35) * crate::PIXELS_PER_METER,
+        SubwayHole {
+            exit: Vec2::new(-0.31, -0.1) * crate::PIXELS_PER_METER,
+            exit_velocity: Vec2::new(0.0, -0.3) * crate::PIXELS_PER_METER,
+            delay: 1.0,
+        },
+    );
+}
+
+fn spawn_subway_hole(commands: &mut Commands, name: &str, position: Vec2, hole: SubwayHole) {
+    let shape_hole = shapes::Circle {
+        radius: BALL_RADIUS * 1.2,
+        center: Vec2::ZERO,
+    };
+    commands.spawn((
+        Name::from(name),
+        ShapeBuilder::with(&shape_hole)
+            .fill(Color::BLACK)
+            .stroke((bevy::color::palettes::css::TEAL, 1.0))
+            .build(),
+        RigidBody::Static,
+        Collider::circle(CAPTURE_RADIUS),
+        Sensor,
+        Layer::Playfield.only(),
+        // under the ball
+        Transform::from_translation(position.extend(-0.4)),
+        hole,
+    ));
+}
+
+fn capture_balls(
+    mut collision_reader: MessageReader<CollisionStart>,
+    holes: Query<(&Name, &SubwayHole)>,
+    mut balls: Query<&mut LinearVelocity, (With<Ball>, Without<InSubway>)>,
+    mut entered: MessageWriter<BallEnteredSubway>,
+    mut commands: Commands,
+) {
+    for event in collision_reader.read() {
+        let (Some(body1), Some(body2)) = (event.body1, event.body2) else {
+            continue;
+        };
+        let (hole, ball) = if holes.contains(body1) {
+            (body1, body2)
+        } else if holes.contains(body2) {
+            (body2, body1)
+        } else {
+            continue;
+        };
+        let (Ok((name, hole)), Ok(mut velocity)) = (holes.get(hole), balls.get_mut(ball)) else {
+            continue;
+        };
+        velocity.0 = Vec2::ZERO;
+        commands.entity(ball).insert((
+            RigidBodyDisabled,
+            ColliderDisabled,
+            Visibility::Hidden,
+            InSubway {
+                hole: name.to_string(),
+                exit: hole.exit,
+                exit_velocity: hole.exit_velocity,
+                remaining: hole.delay,
+            },
+        ));
+        entered.write(BallEnteredSubway {
+            hole: name.to_string(),
+        });
+    }
+}
+
+fn emit_balls(
+    time: Res<Time>,
+    mut balls: Query<(
+        Entity,
+        &mut InSubway,
+        &mut Position,
+        &mut LinearVelocity,
+        &mut Transform,
+    )>,
+    mut left: MessageWriter<BallLeftSubway>,
+    mut commands: Commands,
+) {
+    for (ball, mut subway, mut position, mut velocity, mut transform) in &mut balls {
+        subway.remaining -= time.delta_secs();
+        if subway.remaining > 0.0 {
+            continue;
+        }
+        position.0 = subway.exit;
+        transform.translation = subway.exit.extend(transform.translation.z);
+        velocity.0 = subway.exit_velocity;
+        // The body has to be enabled before the collider: the collider goes back into
+        // the broad phase as soon as ColliderDisabled is removed, and takes whether
+        // its body is disabled from that moment on.
+        commands
+            .entity(ball)
+            .remove::<RigidBodyDisabled>()
+            .remove::<(ColliderDisabled, InSubway)>()
+            .insert((Visibility::Inherited, Roll::rolling(subway.exit_velocity)));
+        left.write(BallLeftSubway {
+            hole: subway.hole.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    #[test]
+    fn ball_comes_back_out_at_the_exit_after_the_delay() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let hole = Vec2::new(-0.05, 0.35) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, hole + Vec2::new(0.0, 20.0), Vec2::new(0.0, -50.0));
+
+        sim.step(32);
+        assert!(sim.app.world().get::<InSubway>(ball).is_some());
+        let (position, _) = sim.ball_state(ball).unwrap();
+        assert!(
+            position.distance(hole) < 25.0,
+            "ball moved on to {position}"
+        );
+
+        sim.step(48);
+        assert!(sim.app.world().get::<InSubway>(ball).is_none());
+        let exit = Vec2::new(-0.31, -0.1) * crate::PIXELS_PER_METER;
+        let (position, velocity) = sim.ball_state(ball).unwrap();
+        assert!(
+            position.distance(exit) < 60.0,
+            "ball came out at {position}"
+        );
+        assert!(velocity.y < 0.0);
+    }
+}
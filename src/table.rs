@@ -0,0 +1,161 @@
+//! Table settings file.
+//!
+//! Solver settings plus the ball, bumper and flipper constants, loadable from
+//! a RON file so a table can be tuned without recompiling. Missing fields keep
+//! their defaults. Changes to [`TableConfig`] at runtime (e.g. from the tuning
+//! panel) apply immediately.
+
+use crate::materials::Materials;
+use avian2d::dynamics::solver::SolverConfig;
+use avian2d::math::Vector;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Standard gravity in m/s^2.
+const STANDARD_GRAVITY: f32 = 9.81;
+
+pub struct TableConfigPlugin;
+
+impl Plugin for TableConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TableConfig>().add_systems(
+            FixedPreUpdate,
+            apply_table_config.run_if(resource_changed::<TableConfig>),
+        );
+    }
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableConfig {
+    /// Playfield pitch in degrees; real machines sit at about 6 to 7.
+    pub slope_degrees: f32,
+    pub substeps: u32,
+    pub contact_frequency_factor: f32,
+    pub restitution_threshold: f32,
+    /// Scales the restitution of every material the ball hits.
+    pub ball_restitution: f32,
+    /// Scales the friction of every material the ball touches.
+    pub ball_friction: f32,
+    /// Ball mass in kg.
+    pub ball_mass: f32,
+    /// Sliding friction between the ball and the playfield.
+    pub table_friction: f32,
+    /// Rolling resistance coefficient of the ball on the playfield.
+    pub rolling_resistance: f32,
+    /// Damping of the ball's spin around the axis out of the playfield.
+    pub ball_spin_damping: f32,
+    /// Impulse a bumper gives the ball on contact.
+    pub bumper_pulse: f32,
+    /// Torque swinging a flipper up while its button is held.
+    pub flipper_torque: f32,
+    /// Torque pulling a released flipper back down.
+    pub flipper_return_torque: f32,
+    pub materials: Materials,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            slope_degrees: 6.5,
+            // without this increased substep count, the ball sinks even deeper though flippers and launcher
+            substeps: 20,
+            // Stiffer contacts so the pulled-down plunger's spring/force load doesn't
+            // leak through Avian's soft contacts and let the ball sink into the head.
+            contact_frequency_factor: 10.0,
+            // Default threshold is ~492 px/s at this length unit, so slow hits don't bounce
+            // and the ball feels glued; lower it so it bounces realistically at low speed.
+            restitution_threshold: 0.05,
+            ball_restitution: 1.0,
+            ball_friction: 1.0,
+            // a standard pinball ball mass is about 80 grams
+            ball_mass: 0.08,
+            table_friction: 0.15,
+            // steel on a waxed playfield
+            rolling_resistance: 0.01,
+            ball_spin_damping: 0.5,
+            bumper_pulse: 35.0,
+            flipper_torque: 1500000.0,
+            // since gravity is not pulling enough we force a torque in the opposite direction
+            flipper_return_torque: 500000.0,
+            materials: Materials::default(),
+        }
+    }
+}
+
+impl TableConfig {
+    /// The part of gravity pulling the ball down the playfield, in px/s^2.
+    pub fn gravity(&self) -> f32 {
+        STANDARD_GRAVITY * self.slope_degrees.to_radians().sin() * crate::PIXELS_PER_METER
+    }
+
+    /// The part of gravity pressing the ball onto the playfield, in px/s^2.
+    pub fn normal_gravity(&self) -> f32 {
+        STANDARD_GRAVITY * self.slope_degrees.to_radians().cos() * crate::PIXELS_PER_METER
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn apply_table_config(
+    config: Res<TableConfig>,
+    mut gravity: ResMut<Gravity>,
+    mut substeps: ResMut<SubstepCount>,
+    mut solver: ResMut<SolverConfig>,
+) {
+    gravity.0 = Vector::NEG_Y * config.gravity();
+    substeps.0 = config.substeps;
+    solver.contact_frequency_factor = config.contact_frequency_factor;
+    solver.restitution_threshold = config.restitution_threshold;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    #[test]
+    fn gravity_is_the_slope_component_of_standard_gravity() {
+        let config = TableConfig::default();
+        let expected = 9.81 * 6.5_f32.to_radians().sin() * crate::PIXELS_PER_METER;
+        assert!((config.gravity() - expected).abs() < 1e-3);
+        // about 546.7 px/s^2 on a 6.5° table
+        assert!((config.gravity() - 546.7).abs() < 0.1);
+
+        let level = TableConfig {
+            slope_degrees: 0.0,
+            ..default()
+        };
+        assert_eq!(level.gravity(), 0.0);
+        assert!((level.normal_gravity() - 9.81 * crate::PIXELS_PER_METER).abs() < 1e-2);
+    }
+
+    #[test]
+    fn changing_the_slope_updates_physics_gravity() {
+        let mut sim = Sim::new();
+        sim.app
+            .world_mut()
+            .resource_mut::<TableConfig>()
+            .slope_degrees = 7.0;
+        sim.step(1);
+
+        let gravity = sim.app.world().resource::<Gravity>().0;
+        let expected = TableConfig {
+            slope_degrees: 7.0,
+            ..default()
+        }
+        .gravity();
+        assert!((gravity.y + expected).abs() < 1e-3 && gravity.x == 0.0);
+    }
+}
@@ -0,0 +1,186 @@
+//! Ball trajectory export.
+//!
+//! After every physics step, writes one JSON line per ball with its position,
+//! velocity and angular velocity, followed by a line for every contact a ball
+//! started or ended during that step. The file is meant for offline analysis,
+//! e.g. comparing exit speeds off bumpers and flippers between tuning changes.
+//!
+//! Enabled by inserting a [`TrajectoryRecorder`] before the app starts.
+
+use crate::ball::Ball;
+use crate::input::InputTick;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub struct TrajectoryPlugin;
+
+impl Plugin for TrajectoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedPostUpdate,
+            record_trajectory
+                .after(PhysicsSystems::Writeback)
+                .run_if(resource_exists::<TrajectoryRecorder>),
+        )
+        .add_systems(
+            Last,
+            flush_trajectory_on_exit.run_if(resource_exists::<TrajectoryRecorder>),
+        );
+    }
+}
+
+/// One line of the export. Entities are identified by their bits, other bodies
+/// in contacts by their `Name` when they have one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TrajectoryRecord {
+    Ball {
+        tick: u64,
+        ball: u64,
+        position: Vec2,
+        velocity: Vec2,
+        angular_velocity: f32,
+    },
+    CollisionStart {
+        tick: u64,
+        ball: u64,
+        other: String,
+        /// Ball speed at the end of the step the contact began in.
+        speed: f32,
+    },
+    CollisionEnd {
+        tick: u64,
+        ball: u64,
+        other: String,
+        speed: f32,
+    },
+}
+
+#[derive(Resource)]
+pub struct TrajectoryRecorder {
+    writer: BufWriter<File>,
+}
+
+impl TrajectoryRecorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    fn write(&mut self, record: &TrajectoryRecord) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+fn record_trajectory(
+    tick: Res<InputTick>,
+    mut recorder: ResMut<TrajectoryRecorder>,
+    balls: Query<(Entity, &Position, &LinearVelocity, &AngularVelocity), With<Ball>>,
+    names: Query<&Name>,
+    mut started: MessageReader<CollisionStart>,
+    mut ended: MessageReader<CollisionEnd>,
+) -> Result<()> {
+    for (ball, position, velocity, angular_velocity) in &balls {
+        recorder.write(&TrajectoryRecord::Ball {
+            tick: tick.0,
+            ball: ball.to_bits(),
+            position: position.0,
+            velocity: velocity.0,
+            angular_velocity: angular_velocity.0,
+        })?;
+    }
+
+    // Splits a contact into the ball, its speed, and a readable name for the other body.
+    let ball_contact = |body1: Option<Entity>, body2: Option<Entity>| {
+        let (body1, body2) = (body1?, body2?);
+        let (ball, other) = if balls.contains(body1) {
+            (body1, body2)
+        } else {
+            (body2, body1)
+        };
+        let (_, _, velocity, _) = balls.get(ball).ok()?;
+        let other = names
+            .get(other)
+            .map_or_else(|_| other.to_string(), |name| name.to_string());
+        Some((ball.to_bits(), velocity.length(), other))
+    };
+
+    for event in started.read() {
+        if let Some((ball, speed, other)) = ball_contact(event.body1, event.body2) {
+            recorder.write(&TrajectoryRecord::CollisionStart {
+                tick: tick.0,
+                ball,
+                other,
+                speed,
+            })?;
+        }
+    }
+    for event in ended.read() {
+        if let Some((ball, speed, other)) = ball_contact(event.body1, event.body2) {
+            recorder.write(&TrajectoryRecord::CollisionEnd {
+                tick: tick.0,
+                ball,
+                other,
+                speed,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn flush_trajectory_on_exit(
+    mut exit: MessageReader<AppExit>,
+    mut recorder: ResMut<TrajectoryRecorder>,
+) {
+    if exit.read().next().is_some()
+        && let Err(err) = recorder.writer.flush()
+    {
+        error!("Failed to write trajectory: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+    use std::fs;
+
+    #[test]
+    fn served_ball_is_recorded_every_step_and_lands_on_the_plunger() {
+        let path = std::env::temp_dir().join("pinball2d_trajectory_test.jsonl");
+        let mut sim = Sim::configured(|app| {
+            app.insert_resource(TrajectoryRecorder::create(&path).unwrap());
+        });
+        sim.step(120);
+        sim.app
+            .world_mut()
+            .resource_mut::<TrajectoryRecorder>()
+            .writer
+            .flush()
+            .unwrap();
+
+        let records: Vec<TrajectoryRecord> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        fs::remove_file(&path).unwrap();
+
+        let ball_samples = records
+            .iter()
+            .filter(|record| matches!(record, TrajectoryRecord::Ball { .. }))
+            .count();
+        assert!(ball_samples >= 120, "only {ball_samples} samples");
+        assert!(records.iter().any(|record| matches!(
+            record,
+            TrajectoryRecord::CollisionStart { other, .. } if other == "Launcher"
+        )));
+    }
+}
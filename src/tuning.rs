@@ -0,0 +1,117 @@
+//! Live tuning panel.
+//!
+//! F4 opens sliders for every [`TableConfig`] value; changes apply on the next
+//! physics tick. "Save" writes the current values to the table file and "Load"
+//! reads them back, so a tuning session ends with a file instead of a list of
+//! constants to copy into the source.
+
+use crate::debug::DebugOverlays;
+use crate::materials::MaterialProperties;
+use crate::table::TableConfig;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use std::path::PathBuf;
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            EguiPrimaryContextPass,
+            tuning_panel.run_if(|overlays: Res<DebugOverlays>| overlays.tuning),
+        );
+    }
+}
+
+/// Where the tuning panel saves and loads the [`TableConfig`].
+#[derive(Resource)]
+pub struct TableFile(pub PathBuf);
+
+fn tuning_panel(
+    mut contexts: EguiContexts,
+    mut config: ResMut<TableConfig>,
+    file: Res<TableFile>,
+) -> Result {
+    let mut edited = config.clone();
+    egui::Window::new("Tuning").show(contexts.ctx_mut()?, |ui| {
+        ui.heading("Solver");
+        ui.add(egui::Slider::new(&mut edited.substeps, 1..=60).text("Substeps"));
+        ui.add(egui::Slider::new(&mut edited.slope_degrees, 0.0..=15.0).text("Slope (°)"));
+        ui.label(format!(
+            "Gravity along the playfield: {:.0} px/s²",
+            edited.gravity()
+        ));
+        ui.add(
+            egui::Slider::new(&mut edited.contact_frequency_factor, 0.5..=30.0)
+                .text("Contact frequency factor"),
+        );
+        ui.add(
+            egui::Slider::new(&mut edited.restitution_threshold, 0.0..=1.0)
+                .text("Restitution threshold"),
+        );
+
+        ui.heading("Ball");
+        ui.add(
+            egui::Slider::new(&mut edited.ball_restitution, 0.0..=1.0).text("Restitution scale"),
+        );
+        ui.add(egui::Slider::new(&mut edited.ball_friction, 0.0..=2.0).text("Friction scale"));
+        ui.add(egui::Slider::new(&mut edited.ball_mass, 0.01..=0.5).text("Mass (kg)"));
+        ui.add(egui::Slider::new(&mut edited.table_friction, 0.0..=1.0).text("Playfield friction"));
+        ui.add(
+            egui::Slider::new(&mut edited.rolling_resistance, 0.0..=0.1).text("Rolling resistance"),
+        );
+        ui.add(egui::Slider::new(&mut edited.ball_spin_damping, 0.0..=5.0).text("Spin damping"));
+
+        ui.heading("Bumpers and flippers");
+        ui.add(egui::Slider::new(&mut edited.bumper_pulse, 0.0..=100.0).text("Bumper pulse"));
+        ui.add(
+            egui::Slider::new(&mut edited.flipper_torque, 0.0..=5_000_000.0).text("Flipper torque"),
+        );
+        ui.add(
+            egui::Slider::new(&mut edited.flipper_return_torque, 0.0..=2_000_000.0)
+                .text("Flipper return torque"),
+        );
+
+        ui.heading("Materials");
+        let materials = &mut edited.materials;
+        material_sliders(ui, "Rubber", &mut materials.rubber);
+        material_sliders(ui, "Metal", &mut materials.metal);
+        material_sliders(ui, "Wood", &mut materials.wood);
+        material_sliders(ui, "Plastic", &mut materials.plastic);
+        material_sliders(ui, "Ball on ball", &mut materials.ball);
+
+        ui.separator();
+        ui.label(file.0.display().to_string());
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                match edited.save(&file.0) {
+                    Ok(()) => info!("Saved tuning to {}", file.0.display()),
+                    Err(err) => error!("Failed to save {}: {err}", file.0.display()),
+                }
+            }
+            if ui.button("Load").clicked() {
+                match TableConfig::load(&file.0) {
+                    Ok(loaded) => edited = loaded,
+                    Err(err) => error!("Failed to load {}: {err}", file.0.display()),
+                }
+            }
+            if ui.button("Defaults").clicked() {
+                edited = TableConfig::default();
+            }
+        });
+    });
+
+    // Only touch the resource on an actual edit so the physics isn't reconfigured every frame.
+    if edited != *config {
+        *config = edited;
+    }
+    Ok(())
+}
+
+fn material_sliders(ui: &mut egui::Ui, name: &str, material: &mut MaterialProperties) {
+    ui.collapsing(name, |ui| {
+        ui.add(egui::Slider::new(&mut material.restitution, 0.0..=1.0).text("Restitution"));
+        ui.add(egui::Slider::new(&mut material.friction, 0.0..=1.0).text("Friction"));
+        ui.add(egui::Slider::new(&mut material.falloff, 0.0..=0.5).text("Falloff (per m/s)"));
+    });
+}
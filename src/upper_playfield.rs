@@ -0,0 +1,217 @@
+//! Upper playfield.
+//!
+//! A raised mini-playfield over the upper left of the table, on
+//! [`Layer::Upper`] with its own walls and a right flipper. A ramp up the left
+//! side drops the ball in at the top; a ball getting past the flipper falls
+//! through the drain at the bottom back onto the main playfield.
+
+use crate::ball::Ball;
+use crate::flippers::spawn_right_flipper;
+use crate::layers::Layer;
+use crate::materials::TableMaterial;
+use crate::ramps::spawn_ramp;
+use crate::walls::{Curve, capsule_chain};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use std::f32::consts::PI;
+
+pub struct UpperPlayfieldPlugin;
+
+impl Plugin for UpperPlayfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_upper_playfield)
+            .add_systems(FixedUpdate, drain_upper_playfield);
+    }
+}
+
+const UPPER_WALL_THICKNESS: f32 = crate::PIXELS_PER_METER * 0.01;
+
+#[derive(Component)]
+struct UpperDrain;
+
+fn spawn_upper_playfield(mut commands: Commands) {
+    let z = Layer::Upper.z();
+    let tint = bevy::color::palettes::css::ORANGE;
+
+    // The plate itself, see-through so the ramp running underneath stays visible.
+    let shape_plate = shapes::Rectangle {
+        extents: Vec2::new(0.18, 0.3) * crate::PIXELS_PER_METER,
+        origin: shapes::RectangleOrigin::Center,
+        radii: None,
+    };
+    commands.spawn((
+        Name::from("Upper Playfield"),
+        ShapeBuilder::with(&shape_plate)
+            .fill(Color::from(tint).with_alpha(0.15))
+            .build(),
+        Transform::from_xyz(
+            crate::PIXELS_PER_METER * -0.24,
+            crate::PIXELS_PER_METER * 0.15,
+            z - 0.5,
+        ),
+    ));
+
+    // Round from beside the flipper pivot, over the top and down the left side,
+    // ending in a slant that leaves a drain gap before the flipper tip.
+    let wall_points = [
+        Vec2::new(-0.16, 0.09),
+        Vec2::new(-0.15, 0.11),
+        Vec2::new(-0.15, 0.3),
+        Vec2::new(-0.33, 0.3),
+        Vec2::new(-0.33, 0.14),
+        Vec2::new(-0.31, 0.06),
+    ]
+    .map(|point| point * crate::PIXELS_PER_METER);
+    let mut path = ShapePath::new().move_to(wall_points[0]);
+    for point in &wall_points[1..] {
+        path = path.line_to(*point);
+    }
+    commands.spawn((
+        Name::from("Upper Playfield Walls"),
+        ShapeBuilder::with(&path)
+            .stroke((tint, UPPER_WALL_THICKNESS))
+            .build(),
+        RigidBody::Static,
+        capsule_chain(&wall_points, UPPER_WALL_THICKNESS),
+        Layer::Upper.only(),
+        TableMaterial::Wood,
+        Transform::from_xyz(0.0, 0.0, z - 0.2),
+    ));
+
+    let flipper = spawn_right_flipper(
+        &mut commands,
+        "Upper Right",
+        Vec3::new(
+            crate::PIXELS_PER_METER * -0.205,
+            crate::PIXELS_PER_METER * 0.08,
+            z - 0.2,
+        ),
+        Vec2::new(0.09, 0.03) * crate::PIXELS_PER_METER,
+        // about as hard to swing as the full-size flippers for the same torque
+        7.7,
+    );
+    commands.entity(flipper).insert(Layer::Upper.only());
+
+    let shape_drain = shapes::Rectangle {
+        extents: Vec2::new(0.18, 0.01) * crate::PIXELS_PER_METER,
+        origin: shapes::RectangleOrigin::Center,
+        radii: None,
+    };
+    commands.spawn((
+        Name::from("Upper Drain"),
+        RigidBody::Static,
+        Collider::rectangle(shape_drain.extents.x, shape_drain.extents.y),
+        Sensor,
+        Layer::Upper.only(),
+        Transform::from_xyz(crate::PIXELS_PER_METER * -0.24, 0.0, z),
+        UpperDrain,
+    ));
+
+    // Up the left side, under the upper playfield, round at the top and down into
+    // it. The mouth is above the left slingshot, where the ball can get to it
+    // from the open playfield.
+    let left_leg = crate::PIXELS_PER_METER * -0.29;
+    let right_leg = crate::PIXELS_PER_METER * -0.2;
+    let top = crate::PIXELS_PER_METER * 0.4;
+    spawn_ramp(
+        &mut commands,
+        "Upper Ramp",
+        &[
+            Curve::Bezier {
+                from: Vec2::new(left_leg, crate::PIXELS_PER_METER * -0.05),
+                ctrl1: Vec2::new(left_leg, crate::PIXELS_PER_METER * 0.05),
+                ctrl2: Vec2::new(left_leg, crate::PIXELS_PER_METER * 0.25),
+                to: Vec2::new(left_leg, top),
+            },
+            Curve::Arc {
+                center: Vec2::new((left_leg + right_leg) / 2.0, top),
+                radius: (right_leg - left_leg) / 2.0,
+                start_angle: PI,
+                sweep: -PI,
+            },
+            Curve::Bezier {
+                from: Vec2::new(right_leg, top),
+                ctrl1: Vec2::new(right_leg, crate::PIXELS_PER_METER * 0.35),
+                ctrl2: Vec2::new(right_leg, crate::PIXELS_PER_METER * 0.29),
+                to: Vec2::new(right_leg, crate::PIXELS_PER_METER * 0.24),
+            },
+        ],
+        Layer::Playfield,
+        Layer::Upper,
+    );
+}
+
+fn drain_upper_playfield(
+    mut collision_reader: MessageReader<CollisionStart>,
+    drains: Query<(), With<UpperDrain>>,
+    mut balls: Query<&mut Transform, With<Ball>>,
+    mut commands: Commands,
+) {
+    for event in collision_reader.read() {
+        let (Some(body1), Some(body2)) = (event.body1, event.body2) else {
+            continue;
+        };
+        let ball = if drains.contains(body1) {
+            body2
+        } else if drains.contains(body2) {
+            body1
+        } else {
+            continue;
+        };
+        if let Ok(mut transform) = balls.get_mut(ball) {
+            Layer::Playfield.enter(&mut commands.entity(ball), &mut transform);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    fn layer_of(sim: &Sim, ball: Entity) -> Option<Layer> {
+        let layers = sim.app.world().get::<CollisionLayers>(ball)?;
+        [Layer::Playfield, Layer::Ramp, Layer::Upper]
+            .into_iter()
+            .find(|layer| *layers == layer.only())
+    }
+
+    #[test]
+    fn ramp_leads_up_onto_the_upper_playfield() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let below_entrance = Vec2::new(-0.29, -0.12) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, below_entrance, Vec2::new(0.0, 1100.0));
+
+        for _ in 0..240 {
+            sim.step(1);
+            if layer_of(&sim, ball) == Some(Layer::Upper) {
+                return;
+            }
+        }
+        panic!("ball never got onto the upper playfield");
+    }
+
+    #[test]
+    fn unflipped_ball_drains_back_to_the_main_playfield() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        sim.app
+            .world_mut()
+            .entity_mut(ball)
+            .insert(Layer::Upper.only());
+        let above_flipper = Vec2::new(-0.21, 0.22) * crate::PIXELS_PER_METER;
+        sim.place_ball(ball, above_flipper, Vec2::ZERO);
+
+        for _ in 0..320 {
+            sim.step(1);
+            if layer_of(&sim, ball) == Some(Layer::Playfield) {
+                let (position, _) = sim.ball_state(ball).unwrap();
+                assert!(position.y < crate::PIXELS_PER_METER * 0.05);
+                return;
+            }
+        }
+        panic!("ball stayed on the upper playfield");
+    }
+}
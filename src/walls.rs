@@ -1,6 +1,8 @@
+use crate::materials::TableMaterial;
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
+use std::f32::consts::PI;
 
 pub struct WallsPlugin;
 
@@ -15,86 +17,94 @@ pub struct BottomWall;
 
 fn spawn_walls(mut commands: Commands) {
     //Spawn outer wall
-    //Spawn top and bottom wall
-    let shape_top_and_bottom_wall = shapes::Rectangle {
+    //Spawn bottom wall
+    let shape_bottom_wall = shapes::Rectangle {
         extents: Vec2::new(
-            crate::PIXELS_PER_METER * 0.73,
+            // wide enough to catch balls draining down the left outlane
+            crate::PIXELS_PER_METER * 0.9,
             crate::PIXELS_PER_METER * 0.03,
         ),
         origin: shapes::RectangleOrigin::Center,
         radii: None,
     };
 
-    //Spawn bottom wall
     let bottom_wall_pos = Vec2::new(0.0, crate::PIXELS_PER_METER * -0.64);
     commands.spawn((
         Name::from("Wall Bottom"),
-        ShapeBuilder::with(&shape_top_and_bottom_wall)
+        ShapeBuilder::with(&shape_bottom_wall)
             .fill(bevy::color::palettes::css::TEAL)
             .build(),
         RigidBody::Static,
-        Collider::rectangle(
-            shape_top_and_bottom_wall.extents.x,
-            shape_top_and_bottom_wall.extents.y,
-        ),
+        Collider::rectangle(shape_bottom_wall.extents.x, shape_bottom_wall.extents.y),
         // we don't want the ball to bounce off the bottom wall, so make it a sensor
         Sensor,
         Transform::from_xyz(bottom_wall_pos.x, bottom_wall_pos.y, 0.0),
         BottomWall,
     ));
 
-    //Spawn top wall
-    let top_wall_pos = Vec2::new(0.0, crate::PIXELS_PER_METER * 0.64);
-    commands.spawn((
-        Name::from("Wall Top"),
-        ShapeBuilder::with(&shape_top_and_bottom_wall)
-            .fill(bevy::color::palettes::css::TEAL)
-            .build(),
-        RigidBody::Static,
-        Collider::rectangle(
-            shape_top_and_bottom_wall.extents.x,
-            shape_top_and_bottom_wall.extents.y,
-        ),
-        Transform::from_xyz(top_wall_pos.x, top_wall_pos.y, 0.0),
-    ));
-
-    //Spawn left and right wall
-    let shape_left_and_right_wall = shapes::Rectangle {
+    //Spawn left wall, down to where it bends out round the left outlane
+    let shape_left_wall = shapes::Rectangle {
         extents: Vec2::new(
             crate::PIXELS_PER_METER * 0.03,
-            crate::PIXELS_PER_METER * 1.3,
+            crate::PIXELS_PER_METER * 0.7,
         ),
         origin: shapes::RectangleOrigin::Center,
         radii: None,
     };
-
-    //Spawn left wall
-    let left_wall_pos = Vec2::new(crate::PIXELS_PER_METER * -0.35, 0.0);
+    let left_wall_pos = Vec2::new(
+        crate::PIXELS_PER_METER * -0.35,
+        crate::PIXELS_PER_METER * 0.3,
+    );
     commands.spawn((
         Name::from("Wall Left"),
-        ShapeBuilder::with(&shape_left_and_right_wall)
+        TableMaterial::Wood,
+        ShapeBuilder::with(&shape_left_wall)
             .fill(bevy::color::palettes::css::TEAL)
             .build(),
         RigidBody::Static,
-        Collider::rectangle(
-            shape_left_and_right_wall.extents.x,
-            shape_left_and_right_wall.extents.y,
-        ),
+        Collider::rectangle(shape_left_wall.extents.x, shape_left_wall.extents.y),
         Transform::from_xyz(left_wall_pos.x, left_wall_pos.y, 0.0),
     ));
 
+    //Spawn the outer wall of the left outlane, which runs down outside the left flipper
+    let outlane_wall_x = crate::PIXELS_PER_METER * -0.44;
+    commands.spawn(curved_wall(
+        "Wall Left Outlane",
+        &[
+            Curve::Bezier {
+                from: Vec2::new(left_wall_pos.x, crate::PIXELS_PER_METER * -0.05),
+                ctrl1: Vec2::new(left_wall_pos.x, crate::PIXELS_PER_METER * -0.12),
+                ctrl2: Vec2::new(outlane_wall_x, crate::PIXELS_PER_METER * -0.12),
+                to: Vec2::new(outlane_wall_x, crate::PIXELS_PER_METER * -0.22),
+            },
+            straight(
+                Vec2::new(outlane_wall_x, crate::PIXELS_PER_METER * -0.22),
+                Vec2::new(outlane_wall_x, crate::PIXELS_PER_METER * -0.65),
+            ),
+        ],
+        WALL_THICKNESS,
+        TableMaterial::Wood,
+    ));
+
     //Spawn right wall
+    let shape_right_wall = shapes::Rectangle {
+        extents: Vec2::new(
+            crate::PIXELS_PER_METER * 0.03,
+            crate::PIXELS_PER_METER * 1.3,
+        ),
+        origin: shapes::RectangleOrigin::Center,
+        radii: None,
+    };
+
     let right_wall_pos = Vec2::new(crate::PIXELS_PER_METER * 0.35, 0.0);
     commands.spawn((
         Name::from("Wall Right"),
-        ShapeBuilder::with(&shape_left_and_right_wall)
+        TableMaterial::Wood,
+        ShapeBuilder::with(&shape_right_wall)
             .fill(bevy::color::palettes::css::TEAL)
             .build(),
         RigidBody::Static,
-        Collider::rectangle(
-            shape_left_and_right_wall.extents.x,
-            shape_left_and_right_wall.extents.y,
-        ),
+        Collider::rectangle(shape_right_wall.extents.x, shape_right_wall.extents.y),
         Transform::from_xyz(right_wall_pos.x, right_wall_pos.y, 0.0),
     ));
 
@@ -102,18 +112,17 @@ fn spawn_walls(mut commands: Commands) {
     let shape_launcher_wall = shapes::Rectangle {
         extents: Vec2::new(
             crate::PIXELS_PER_METER * 0.03,
-            crate::PIXELS_PER_METER * 0.5,
+            // up to just below the shooter lane gate
+            crate::PIXELS_PER_METER * 0.78,
         ),
         origin: shapes::RectangleOrigin::Center,
         radii: None,
     };
 
-    let launcher_wall_pos = Vec2::new(
-        crate::PIXELS_PER_METER * 0.25,
-        crate::PIXELS_PER_METER * -0.36,
-    );
+    let launcher_wall_pos = Vec2::new(LAUNCHER_WALL_X, crate::PIXELS_PER_METER * -0.22);
     commands.spawn((
         Name::from("Wall Launcher"),
+        TableMaterial::Wood,
         ShapeBuilder::with(&shape_launcher_wall)
             .fill(bevy::color::palettes::css::TEAL)
             .build(),
@@ -122,46 +131,246 @@ fn spawn_walls(mut commands: Commands) {
         Transform::from_xyz(launcher_wall_pos.x, launcher_wall_pos.y, 0.0),
     ));
 
-    //Spawn upper right obstruction
-    let shape_upper_right_obstruction = shapes::Polygon {
-        points: vec![
-            Vec2::new(0.0, 0.0),
-            Vec2::new(0.0, crate::PIXELS_PER_METER * 0.25),
-            Vec2::new(
-                crate::PIXELS_PER_METER * -0.2,
-                crate::PIXELS_PER_METER * 0.25,
-            ),
-        ],
-        closed: true,
-    };
+    //Spawn top arch, which also turns a launched ball from the shooter lane onto the playfield
+    commands.spawn(curved_wall(
+        "Wall Top Arch",
+        &[Curve::Arc {
+            center: Vec2::new(0.0, crate::PIXELS_PER_METER * 0.29),
+            radius: crate::PIXELS_PER_METER * 0.35,
+            start_angle: 0.0,
+            sweep: PI,
+        }],
+        WALL_THICKNESS,
+        TableMaterial::Wood,
+    ));
 
-    let upper_right_obstruction_pos = Vec2::new(
-        crate::PIXELS_PER_METER * 0.37,
-        crate::PIXELS_PER_METER * 0.4,
-    );
+    //Spawn orbit guide, the inner side of the lane running around under the arch
+    commands.spawn(curved_wall(
+        "Orbit Guide",
+        &[Curve::Arc {
+            center: Vec2::new(0.0, crate::PIXELS_PER_METER * 0.29),
+            radius: crate::PIXELS_PER_METER * 0.25,
+            start_angle: 30.0_f32.to_radians(),
+            sweep: 120.0_f32.to_radians(),
+        }],
+        GUIDE_THICKNESS,
+        TableMaterial::Metal,
+    ));
+}
 
-    commands.spawn((
-        Name::from("Wall Upper Right Obstruction"),
-        ShapeBuilder::with(&shape_upper_right_obstruction)
-            .fill(bevy::color::palettes::css::TEAL)
+/// Each curve is collided as this many straight pieces.
+const CURVE_SEGMENTS: usize = 24;
+
+const WALL_THICKNESS: f32 = crate::PIXELS_PER_METER * 0.03;
+
+/// Middle of the wall between the playfield and the shooter lane.
+const LAUNCHER_WALL_X: f32 = crate::PIXELS_PER_METER * 0.25;
+/// The launcher wall's face towards the playfield.
+pub(crate) const LAUNCHER_WALL_INNER_X: f32 = LAUNCHER_WALL_X - WALL_THICKNESS / 2.0;
+
+/// Metal lane guides are thinner than the wooden walls.
+pub(crate) const GUIDE_THICKNESS: f32 = crate::PIXELS_PER_METER * 0.01;
+
+/// A curved piece of wall, in table coordinates.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Curve {
+    /// Arc around `center`, turning counter-clockwise by `sweep` radians (clockwise if negative).
+    Arc {
+        center: Vec2,
+        radius: f32,
+        start_angle: f32,
+        sweep: f32,
+    },
+    /// Cubic Bézier from `from` to `to`.
+    Bezier {
+        from: Vec2,
+        ctrl1: Vec2,
+        ctrl2: Vec2,
+        to: Vec2,
+    },
+}
+
+impl Curve {
+    /// Point at `t` from 0 (start) to 1 (end).
+    pub fn point(&self, t: f32) -> Vec2 {
+        match *self {
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => center + radius * Vec2::from_angle(start_angle + sweep * t),
+            Curve::Bezier {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let u = 1.0 - t;
+                from * u * u * u
+                    + 3.0 * ctrl1 * u * u * t
+                    + 3.0 * ctrl2 * u * t * t
+                    + to * t * t * t
+            }
+        }
+    }
+
+    /// Direction of travel at `t`, normalized.
+    pub fn tangent(&self, t: f32) -> Vec2 {
+        match *self {
+            Curve::Arc {
+                start_angle, sweep, ..
+            } => Vec2::from_angle(start_angle + sweep * t).perp() * sweep.signum(),
+            Curve::Bezier {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let u = 1.0 - t;
+                (3.0 * u * u * (ctrl1 - from)
+                    + 6.0 * u * t * (ctrl2 - ctrl1)
+                    + 3.0 * t * t * (to - ctrl2))
+                    .normalize()
+            }
+        }
+    }
+
+    /// Continues `path`, which must already be at the start of the curve.
+    fn extend_path(&self, path: ShapePath) -> ShapePath {
+        match *self {
+            Curve::Arc {
+                center,
+                radius,
+                sweep,
+                ..
+            } => path.arc(center, Vec2::splat(radius), sweep, 0.0),
+            Curve::Bezier {
+                ctrl1, ctrl2, to, ..
+            } => path.cubic_bezier_to(ctrl1, ctrl2, to),
+        }
+    }
+}
+
+/// A straight piece, as a Bézier so it can be chained with other curves.
+pub(crate) fn straight(from: Vec2, to: Vec2) -> Curve {
+    Curve::Bezier {
+        from,
+        ctrl1: from.lerp(to, 1.0 / 3.0),
+        ctrl2: from.lerp(to, 2.0 / 3.0),
+        to,
+    }
+}
+
+/// A wall along `curves`, which follow on from each other. It is drawn as a
+/// lyon path and collided as a chain of capsules as thick as the wall, so the
+/// ball meets the wall where it is drawn.
+pub(crate) fn curved_wall(
+    name: &str,
+    curves: &[Curve],
+    thickness: f32,
+    material: TableMaterial,
+) -> impl Bundle + use<> {
+    let mut path = ShapePath::new().move_to(curves[0].point(0.0));
+    for curve in curves {
+        path = curve.extend_path(path);
+    }
+
+    (
+        Name::from(name),
+        material,
+        ShapeBuilder::with(&path)
+            .stroke((bevy::color::palettes::css::TEAL, thickness))
             .build(),
         RigidBody::Static,
-        Collider::polyline(
-            vec![
-                Vec2::new(0.0, 0.0),
-                Vec2::new(0.0, crate::PIXELS_PER_METER * 0.25),
-                Vec2::new(
-                    crate::PIXELS_PER_METER * -0.2,
-                    crate::PIXELS_PER_METER * 0.25,
-                ),
-                Vec2::new(0.0, 0.0),
-            ],
-            None,
-        ),
-        Transform::from_xyz(
-            upper_right_obstruction_pos.x,
-            upper_right_obstruction_pos.y,
-            0.0,
-        ),
-    ));
+        capsule_chain(&offset_points(curves, 0.0), thickness),
+        Transform::default(),
+    )
+}
+
+/// Points along `curves`, which follow on from each other, moved `offset`
+/// sideways; to the left of the direction of travel if positive.
+pub(crate) fn offset_points(curves: &[Curve], offset: f32) -> Vec<Vec2> {
+    let mut points = Vec::new();
+    for (index, curve) in curves.iter().enumerate() {
+        // Each curve starts where the previous one ended.
+        let first = if index == 0 { 0 } else { 1 };
+        points.extend((first..=CURVE_SEGMENTS).map(|i| {
+            let t = i as f32 / CURVE_SEGMENTS as f32;
+            curve.point(t) + curve.tangent(t).perp() * offset
+        }));
+    }
+    points
+}
+
+/// A collider following `points`, as a chain of capsules `thickness` across.
+pub(crate) fn capsule_chain(points: &[Vec2], thickness: f32) -> Collider {
+    // A straight run is one capsule, so a ball rolling along it has no joints to catch on.
+    let mut corners = vec![points[0]];
+    for (&point, &next) in points[1..].iter().zip(&points[2..]) {
+        let (before, after) = (point - *corners.last().unwrap(), next - point);
+        if before.perp_dot(after).abs() > 1e-4 * before.length() * after.length() {
+            corners.push(point);
+        }
+    }
+    corners.push(points[points.len() - 1]);
+
+    Collider::compound(
+        corners
+            .windows(2)
+            .map(|ends| {
+                (
+                    Position::default(),
+                    Rotation::default(),
+                    Collider::capsule_endpoints(thickness / 2.0, ends[0], ends[1]),
+                )
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::Sim;
+
+    #[test]
+    fn arch_turns_launched_ball_onto_the_playfield() {
+        let mut sim = Sim::new();
+        let ball = sim.ball();
+        let lane = Vec2::new(crate::PIXELS_PER_METER * 0.3, crate::PIXELS_PER_METER * 0.1);
+        sim.place_ball(ball, lane, Vec2::new(0.0, 1500.0));
+        sim.step(40);
+
+        let (position, velocity) = sim.ball_state(ball).unwrap();
+        assert!(
+            position.x < crate::PIXELS_PER_METER * 0.2 && velocity.x < 0.0,
+            "ball at {position} moving {velocity}"
+        );
+    }
+
+    #[test]
+    fn curves_start_and_end_where_they_say() {
+        let arc = Curve::Arc {
+            center: Vec2::ZERO,
+            radius: 10.0,
+            start_angle: 0.0,
+            sweep: PI / 2.0,
+        };
+        let points = offset_points(&[arc], 0.0);
+        assert!(points[0].abs_diff_eq(Vec2::new(10.0, 0.0), 1e-4));
+        assert!(points[CURVE_SEGMENTS].abs_diff_eq(Vec2::new(0.0, 10.0), 1e-4));
+        // Left of a counter-clockwise arc is towards its center.
+        let inside = offset_points(&[arc], 1.0);
+        assert!(inside[0].abs_diff_eq(Vec2::new(9.0, 0.0), 1e-4));
+
+        let bezier = Curve::Bezier {
+            from: Vec2::ZERO,
+            ctrl1: Vec2::new(1.0, 5.0),
+            ctrl2: Vec2::new(4.0, 5.0),
+            to: Vec2::new(5.0, 0.0),
+        };
+        assert!(bezier.point(0.0).abs_diff_eq(Vec2::ZERO, 1e-4));
+        assert!(bezier.point(1.0).abs_diff_eq(Vec2::new(5.0, 0.0), 1e-4));
+    }
 }